@@ -0,0 +1,159 @@
+use chrono::{Local, NaiveDateTime};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/* ---------- Atomic writes ---------- */
+
+/// Writes `path` through a temporary file in the same directory that is
+/// renamed into place, so a crash mid-save leaves the old file intact.
+pub fn write_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut File) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let result = File::create(&tmp).and_then(|mut f| {
+        write(&mut f)?;
+        f.sync_all()
+    });
+    match result.and_then(|_| fs::rename(&tmp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp);
+            Err(e)
+        }
+    }
+}
+
+/* ---------- Backups ---------- */
+
+const STAMP: &str = "%Y%m%d-%H%M%S";
+
+/// A timestamped copy of the data file.
+pub struct Backup {
+    pub path: PathBuf,
+    pub taken: NaiveDateTime,
+    pub bytes: u64,
+}
+
+/// `backups/` next to the data file.
+fn backup_dir(path: &Path) -> PathBuf {
+    path.parent().unwrap_or(Path::new("")).join("backups")
+}
+
+/// "work_data-" and ".csv" for "work_data.csv"; backups are named prefix +
+/// timestamp + suffix, so each format of a file has its own backups.
+fn backup_affixes(path: &Path) -> (String, String) {
+    let stem = path
+        .file_stem()
+        .map_or("data".into(), |s| s.to_string_lossy());
+    let ext = path
+        .extension()
+        .map_or(String::new(), |e| format!(".{}", e.to_string_lossy()));
+    (format!("{}-", stem), ext)
+}
+
+/// Copies the current file at `path` into the backup folder and deletes the
+/// oldest backups beyond `keep`. Does nothing if there is no file yet.
+pub fn take_backup(path: &Path, keep: usize) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir)?;
+    let (prefix, suffix) = backup_affixes(path);
+    let name = format!("{}{}{}", prefix, Local::now().format(STAMP), suffix);
+    fs::copy(path, dir.join(name))?;
+
+    for old in list_backups(path).into_iter().skip(keep.max(1)) {
+        fs::remove_file(old.path)?;
+    }
+    Ok(())
+}
+
+/// Backups of the file at `path`, newest first.
+pub fn list_backups(path: &Path) -> Vec<Backup> {
+    let (prefix, suffix) = backup_affixes(path);
+    let Ok(dir) = fs::read_dir(backup_dir(path)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<Backup> = dir
+        .flatten()
+        .filter_map(|item| {
+            let name = item.file_name().to_string_lossy().into_owned();
+            let stamp = name.strip_prefix(&prefix)?.strip_suffix(&suffix)?;
+            let taken = NaiveDateTime::parse_from_str(stamp, STAMP).ok()?;
+            let bytes = item.metadata().ok()?.len();
+            Some(Backup {
+                path: item.path(),
+                taken,
+                bytes,
+            })
+        })
+        .collect();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.taken));
+    backups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn failed_atomic_write_keeps_the_old_file() {
+        let dir = temp_dir("write_atomic");
+        let path = dir.join("work_data.json");
+        write_atomic(&path, |f| f.write_all(b"old")).unwrap();
+        let failed = write_atomic(&path, |f| {
+            f.write_all(b"half")?;
+            Err(io::Error::other("disk full"))
+        });
+        assert!(failed.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        write_atomic(&path, |f| f.write_all(b"new")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        // 임시 파일은 남지 않음
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prunes_and_lists_backups_of_one_format_only() {
+        let dir = temp_dir("backups");
+        let csv = dir.join("work_data.csv");
+        let db = dir.join("work_data.db");
+        fs::write(&csv, "csv").unwrap();
+        fs::create_dir_all(dir.join("backups")).unwrap();
+        for name in [
+            "work_data-20250101-090000.csv",
+            "work_data-20250102-090000.csv",
+            "work_data-20250101-090000.db",
+            "work_data-notes.csv",
+        ] {
+            fs::write(dir.join("backups").join(name), name).unwrap();
+        }
+
+        take_backup(&csv, 2).unwrap();
+        let backups = list_backups(&csv);
+        assert_eq!(backups.len(), 2);
+        assert_eq!(fs::read_to_string(&backups[0].path).unwrap(), "csv");
+        assert!(backups[1].path.ends_with("work_data-20250102-090000.csv"));
+        assert!(!dir.join("backups/work_data-20250101-090000.csv").exists());
+        assert!(dir.join("backups/work_data-notes.csv").exists());
+
+        let backups = list_backups(&db);
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].taken.to_string(), "2025-01-01 09:00:00");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
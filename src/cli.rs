@@ -0,0 +1,501 @@
+use crate::history::Command;
+use crate::money::Money;
+use crate::pay::{
+    calculate_ledger, calculate_weekly_allowances, find_job, priced, total_pay, PaySummary,
+};
+use crate::storage::{self, Format};
+use crate::times::{hhmm, hhmm_on, EntryError};
+use crate::{last_day, App, EntryForm, WorkEntry};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Commands (run without one to open the calendar):
+  add <date> <start> <end>     Log a shift, e.g. add 2024-05-03 9:00 18:00.
+                               An end such as 06:00+1 is on the next day.
+      --break <from>-<to>[ paid]  A break within the shift; repeatable.
+      --job <name>                Job to log it for (default: the active job).
+      --rate <amount>             Hourly rate for this shift only.
+      --force                     Allow a shift longer than the long-shift
+                                  limit, or saving after unreadable lines.
+  list [--month YYYY-MM]       Shifts of a month (default: this month).
+  remove <date> [<n>]          Remove shift n of a date, numbered as in list.
+  report [--month YYYY-MM | --range <from> <to>]
+                               Hours and pay per job.
+  export <path> [--force]      Copy the data to a new .json, .db or .csv file.
+
+Options for every command:
+  --profile <name>             Use the data of another profile.
+  --file <path>                Use this data file.
+
+Dates are YYYY-MM-DD, today or yesterday.
+";
+
+/// Runs the command in `args` without opening a window and returns the
+/// exit code. Results go to stdout, errors and status messages to stderr.
+pub fn run(args: &[String]) -> i32 {
+    match parse_args(args).and_then(|args| dispatch(&args)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    }
+}
+
+/* ---------- Arguments ---------- */
+
+#[derive(Default)]
+struct Args {
+    /// The command and its positional arguments.
+    words: Vec<String>,
+    profile: Option<String>,
+    file: Option<PathBuf>,
+    month: Option<String>,
+    range: Option<(String, String)>,
+    job: Option<String>,
+    rate: Option<String>,
+    breaks: Vec<String>,
+    force: bool,
+    help: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = || {
+            it.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--profile" => parsed.profile = Some(value()?),
+            "--file" => parsed.file = Some(value()?.into()),
+            "--month" => parsed.month = Some(value()?),
+            "--range" => parsed.range = Some((value()?, value()?)),
+            "--job" => parsed.job = Some(value()?),
+            "--rate" => parsed.rate = Some(value()?),
+            "--break" => parsed.breaks.push(value()?),
+            "--force" => parsed.force = true,
+            "-h" | "--help" => parsed.help = true,
+            s if s.starts_with("--") => return Err(format!("unknown option {}", s)),
+            _ => parsed.words.push(arg.clone()),
+        }
+    }
+    if parsed.month.is_some() && parsed.range.is_some() {
+        return Err("give either --month or --range, not both".into());
+    }
+    Ok(parsed)
+}
+
+type Handler = fn(&mut App, &Args, &[String]) -> Result<(), String>;
+
+fn dispatch(args: &Args) -> Result<(), String> {
+    let Some((command, rest)) = args.words.split_first() else {
+        if args.help {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+        return Err("no command given (see --help)".into());
+    };
+    let handler: Handler = match command.as_str() {
+        "help" => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+        _ if args.help => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+        "add" => add,
+        "list" => list,
+        "remove" => remove,
+        "report" => report,
+        "export" => export,
+        other => return Err(format!("unknown command \"{}\" (see --help)", other)),
+    };
+    let mut app = open(args)?;
+    handler(&mut app, args, rest)
+}
+
+/// The app with the data file of the chosen profile or `--file` loaded.
+/// The workspace file is only read, so the profile and recent files the
+/// window opens with are left as they were.
+fn open(args: &Args) -> Result<App, String> {
+    let mut app = App::default();
+    if let Some(e) = &app.workspace.load_error {
+        eprintln!("⚠ Could not read {}", e);
+    }
+    if let Some(name) = &args.profile {
+        if !app.workspace.profiles.iter().any(|p| p.name == *name) {
+            return Err(format!("there is no profile called \"{}\"", name));
+        }
+        app.workspace.current = name.clone();
+    }
+    let path = args
+        .file
+        .clone()
+        .unwrap_or_else(|| app.workspace.start_file());
+    app.load_file(&path)?;
+    if let Some(report) = &app.load_report {
+        for r in &report.rejected {
+            eprintln!("⚠ {} line {}: {}", report.path, r.line, r.reason);
+        }
+    }
+    Ok(app)
+}
+
+/// Saves after a change. Lines the load skipped would be dropped from the
+/// file, so that needs `--force`.
+fn save(app: &mut App, args: &Args) -> Result<(), String> {
+    let skipped = app.load_report.as_ref().map_or(0, |r| r.rejected.len());
+    if skipped > 0 && !args.force {
+        return Err(format!(
+            "{} line(s) could not be read and would be dropped; add --force to save anyway",
+            skipped
+        ));
+    }
+    if app.save_data() {
+        if let Some(warning) = app.error_toast.take() {
+            eprintln!("⚠ {}", warning);
+        }
+        Ok(())
+    } else {
+        Err(app
+            .error_toast
+            .take()
+            .unwrap_or_else(|| "Save failed".into()))
+    }
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    let today = Local::now().naive_local().date();
+    match s {
+        "today" => Ok(today),
+        "yesterday" => Ok(today - Duration::days(1)),
+        _ => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| format!("\"{}\" is not a date (YYYY-MM-DD)", s)),
+    }
+}
+
+/// First and last day of `--month`, or of this month.
+fn month(args: &Args) -> Result<(NaiveDate, NaiveDate), String> {
+    let first = match &args.month {
+        Some(m) => NaiveDate::parse_from_str(&format!("{}-01", m), "%Y-%m-%d")
+            .map_err(|_| format!("\"{}\" is not a month (YYYY-MM)", m))?,
+        None => Local::now().naive_local().date().with_day(1).unwrap(),
+    };
+    let last = first
+        .with_day(last_day(first.year(), first.month()))
+        .unwrap();
+    Ok((first, last))
+}
+
+/// `--range` if given, otherwise the month.
+fn period(args: &Args) -> Result<(NaiveDate, NaiveDate), String> {
+    let Some((from, to)) = &args.range else {
+        return month(args);
+    };
+    let (from, to) = (parse_date(from)?, parse_date(to)?);
+    if to < from {
+        return Err(format!(
+            "the range ends before it starts ({} – {})",
+            from, to
+        ));
+    }
+    Ok((from, to))
+}
+
+/* ---------- Commands ---------- */
+
+fn add(app: &mut App, args: &Args, rest: &[String]) -> Result<(), String> {
+    let [date, start, end] = rest else {
+        return Err("usage: add <date> <start> <end>".into());
+    };
+    let date = parse_date(date)?;
+    let (end, end_next_day) = match end.strip_suffix("+1") {
+        Some(end) => (end, true),
+        None => (end.as_str(), false),
+    };
+    let job = match &args.job {
+        Some(name) => app
+            .jobs
+            .iter()
+            .find(|j| j.name.eq_ignore_ascii_case(name.trim()))
+            .map(|j| j.id)
+            .ok_or_else(|| format!("there is no job called \"{}\"", name))?,
+        None => app.job().id,
+    };
+    let rate = match &args.rate {
+        Some(r) => Some(
+            r.parse::<f64>()
+                .ok()
+                .filter(|r| r.is_finite() && *r >= 0.0)
+                .ok_or_else(|| format!("\"{}\" is not a rate", r))?,
+        ),
+        None => None,
+    };
+    let form = EntryForm {
+        start: start.clone(),
+        end: end.into(),
+        end_next_day,
+        rate_override: rate.is_some(),
+        rate: rate.unwrap_or_default(),
+        ..EntryForm::default()
+    };
+    let mut entry = app
+        .entry_from_form(&form, date, job, None)
+        .map_err(|e| match e {
+            EntryError::EndBeforeStart => {
+                "End is before start. Write the end as HH:MM+1 for an overnight shift.".into()
+            }
+            e => e.to_string(),
+        })?;
+    entry.breaks = storage::parse_breaks(&args.breaks.join(";"), entry.start, entry.end)
+        .map_err(|e| e.to_string())?;
+
+    let hours = (entry.end - entry.start).num_minutes() as f64 / 60.0;
+    if hours > app.max_shift_hours && !args.force {
+        return Err(format!(
+            "this shift is {:.1}h, longer than {:.0}h; add --force to log it anyway",
+            hours, app.max_shift_hours
+        ));
+    }
+    if !app.has_paid_time(date, &entry) {
+        return Err("No paid time left after breaks.".into());
+    }
+    let index = app.entries.get(&date).map_or(0, Vec::len);
+    app.execute(Command::InsertEntry { date, index, entry });
+    save(app, args)?;
+
+    let ledger = calculate_ledger(&app.entries, &app.jobs, &app.holidays, app.currency);
+    let entry = &app.entries[&date][index];
+    println!(
+        "Added {}",
+        line(app, date, index + 1, entry, ledger[&date][index].as_ref())
+    );
+    Ok(())
+}
+
+fn list(app: &mut App, args: &Args, rest: &[String]) -> Result<(), String> {
+    if !rest.is_empty() {
+        return Err("usage: list [--month YYYY-MM]".into());
+    }
+    let (first, last) = month(args)?;
+    let ledger = calculate_ledger(&app.entries, &app.jobs, &app.holidays, app.currency);
+    let mut dates: Vec<NaiveDate> = app
+        .entries
+        .keys()
+        .filter(|d| (first..=last).contains(*d))
+        .copied()
+        .collect();
+    dates.sort();
+    if dates.is_empty() {
+        println!("No entries in {}.", first.format("%Y-%m"));
+    }
+    for date in dates {
+        for (i, entry) in app.entries[&date].iter().enumerate() {
+            let summary = ledger.get(&date).and_then(|l| l[i].as_ref());
+            println!("{}", line(app, date, i + 1, entry, summary));
+        }
+    }
+    Ok(())
+}
+
+/// One entry as `list` shows it: date, number, times, job, hours and pay.
+fn line(
+    app: &App,
+    date: NaiveDate,
+    n: usize,
+    entry: &WorkEntry,
+    summary: Option<&PaySummary>,
+) -> String {
+    let job = find_job(&app.jobs, entry.job).map_or("?", |j| j.name.as_str());
+    let times = format!("{}-{}", hhmm(entry.start.time()), hhmm_on(entry.end, date));
+    let pay = match summary.map(PaySummary::whole) {
+        Some(s) => format!(
+            "{:>6.2}h {:>12}",
+            s.total_hours(),
+            app.currency.format(s.total_pay)
+        ),
+        None => "unpriced".into(),
+    };
+    let mut line = format!("{} {:>2}  {:<13} {:<16} {}", date, n, times, job, pay);
+    if !entry.breaks.is_empty() {
+        line += &format!("  breaks {}", storage::format_breaks(&entry.breaks));
+    }
+    line
+}
+
+fn remove(app: &mut App, args: &Args, rest: &[String]) -> Result<(), String> {
+    let (date, n) = match rest {
+        [date] => (date, None),
+        [date, n] => (date, Some(n)),
+        _ => return Err("usage: remove <date> [<n>]".into()),
+    };
+    let date = parse_date(date)?;
+    let count = app.entries.get(&date).map_or(0, Vec::len);
+    let index = match n {
+        Some(n) => n
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=count).contains(n))
+            .ok_or_else(|| format!("{} has no entry {}", date, n))?,
+        None if count == 1 => 1,
+        None if count == 0 => return Err(format!("{} has no entries", date)),
+        None => {
+            return Err(format!(
+                "{} has {} entries; say which one (see list)",
+                date, count
+            ))
+        }
+    } - 1;
+    let ledger = calculate_ledger(&app.entries, &app.jobs, &app.holidays, app.currency);
+    let entry = app.entries[&date][index].clone();
+    let removed = line(app, date, index + 1, &entry, ledger[&date][index].as_ref());
+    app.execute(Command::RemoveEntry { date, index, entry });
+    save(app, args)?;
+    println!("Removed {}", removed);
+    Ok(())
+}
+
+fn report(app: &mut App, args: &Args, rest: &[String]) -> Result<(), String> {
+    if !rest.is_empty() {
+        return Err("usage: report [--month YYYY-MM | --range <from> <to>]".into());
+    }
+    let (from, to) = period(args)?;
+    let in_period = |d: NaiveDate| (from..=to).contains(&d);
+    let ledger = calculate_ledger(&app.entries, &app.jobs, &app.holidays, app.currency);
+    let allowances = calculate_weekly_allowances(
+        &app.entries,
+        &ledger,
+        &app.jobs,
+        &app.holidays,
+        app.currency,
+    );
+    let items: Vec<_> = priced(&app.entries, &ledger)
+        .filter(|(d, _, _)| in_period(*d))
+        .collect();
+
+    let format = |m: Money| app.currency.format(m);
+    println!("{} – {}", from, to);
+    println!(
+        "{:<16} {:>8} {:>8} {:>14} {:>14}",
+        "Job", "Hours", "Overtime", "Pay", "Allowance"
+    );
+    let (mut hours, mut overtime, mut pay, mut allowance) = (0.0, 0.0, Money::ZERO, Money::ZERO);
+    for job in &app.jobs {
+        let mine = || items.iter().copied().filter(|(_, e, _)| e.job == job.id);
+        // 주휴수당은 주의 마지막 날이 속한 기간에 포함
+        let job_allowance: Money = allowances
+            .iter()
+            .filter(|((j, week), _)| *j == job.id && in_period(*week + Duration::days(6)))
+            .map(|(_, amount)| *amount)
+            .sum();
+        if mine().next().is_none() && job_allowance == Money::ZERO {
+            continue;
+        }
+        let job_hours: f64 = mine().map(|(_, _, s)| s.total_hours()).sum();
+        let job_overtime: f64 = mine().map(|(_, _, s)| s.overtime_hours).sum();
+        let job_pay = total_pay(mine(), &app.jobs);
+        println!(
+            "{:<16} {:>8.2} {:>8.2} {:>14} {:>14}",
+            job.name,
+            job_hours,
+            job_overtime,
+            format(job_pay),
+            format(job_allowance)
+        );
+        hours += job_hours;
+        overtime += job_overtime;
+        pay += job_pay;
+        allowance += job_allowance;
+    }
+    println!(
+        "{:<16} {:>8.2} {:>8.2} {:>14} {:>14}",
+        "Total",
+        hours,
+        overtime,
+        format(pay),
+        format(allowance)
+    );
+    println!("Pay incl. allowance: {}", format(pay + allowance));
+    Ok(())
+}
+
+fn export(app: &mut App, args: &Args, rest: &[String]) -> Result<(), String> {
+    let [path] = rest else {
+        return Err("usage: export <path> [--force]".into());
+    };
+    let target = std::path::absolute(path).map_err(|e| format!("{}: {}", path, e))?;
+    if target == app.storage.path() {
+        return Err(format!("{} is the file in use", target.display()));
+    }
+    if target.exists() && !args.force {
+        return Err(format!(
+            "{} already exists; add --force to replace it",
+            target.display()
+        ));
+    }
+    // 옆에 다 쓴 뒤에 바꿔 넣어서, 실패해도 있던 파일은 그대로
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = target.with_file_name(format!("~{}", name));
+    let files = [
+        (storage::settings_path(&tmp), storage::settings_path(&target)),
+        (tmp.clone(), target.clone()),
+    ];
+    for (from, _) in &files {
+        let _ = std::fs::remove_file(from);
+    }
+    drop(app.write_copy(&tmp)?);
+    for (from, to) in files.iter().filter(|(from, _)| from.exists()) {
+        std::fs::rename(from, to).map_err(|e| {
+            let _ = std::fs::remove_file(from);
+            format!("{}: {}", to.display(), e)
+        })?;
+    }
+    let count: usize = app.entries.values().map(Vec::len).sum();
+    println!(
+        "Exported {} entries to {} ({})",
+        count,
+        target.display(),
+        Format::of(&target).label()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Args, String> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn reads_options_anywhere_on_the_line() {
+        let args = parse("--profile Cafe export out.db --force --file data/w.csv").unwrap();
+        assert_eq!(args.words, ["export", "out.db"]);
+        assert_eq!(args.profile.as_deref(), Some("Cafe"));
+        assert_eq!(args.file, Some(PathBuf::from("data/w.csv")));
+        assert!(args.force);
+
+        let args = parse("add today 9:00 18:00 --break 12:00-13:00 --break 15:00-15:10").unwrap();
+        assert!(!args.force && args.profile.is_none() && args.file.is_none());
+        assert_eq!(args.breaks, ["12:00-13:00", "15:00-15:10"]);
+    }
+
+    #[test]
+    fn rejects_bad_options_and_commands() {
+        assert_eq!(parse("list --profile").err().unwrap(), "--profile needs a value");
+        assert_eq!(parse("list --file").err().unwrap(), "--file needs a value");
+        assert_eq!(parse("list --forse").err().unwrap(), "unknown option --forse");
+        assert!(parse("report --month 2025-03 --range 2025-03-01 2025-03-31").is_err());
+        // 알 수 없는 명령은 파일을 열기 전에 거절
+        let err = dispatch(&parse("lsit --month 2025-03").unwrap()).unwrap_err();
+        assert_eq!(err, "unknown command \"lsit\" (see --help)");
+        assert!(dispatch(&parse("--force").unwrap()).is_err());
+    }
+}
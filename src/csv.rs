@@ -0,0 +1,187 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/* ---------- Reading ---------- */
+
+/// One CSV record and the line it starts on (counted from 1).
+pub struct Record {
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+/// A line that could not be loaded, with the reason shown to the user.
+#[derive(Clone, Debug)]
+pub struct Rejected {
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Splits CSV text into records. Fields may be quoted with `"`; a quoted
+/// field can hold commas, line breaks and doubled quotes (`""`). Blank lines
+/// are skipped and a leading byte order mark is ignored.
+pub fn parse(text: &str) -> Vec<Result<Record, Rejected>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start_line = line;
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut error = None;
+        loop {
+            match chars.next() {
+                None => break,
+                Some('"') if quoted => {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        field.push('"');
+                    } else {
+                        quoted = false;
+                    }
+                }
+                Some('"') if field.is_empty() => quoted = true,
+                Some('\n') if quoted => {
+                    line += 1;
+                    field.push('\n');
+                }
+                Some(c) if quoted => field.push(c),
+                Some(',') => fields.push(std::mem::take(&mut field)),
+                Some('\r') if chars.peek() == Some(&'\n') => {}
+                Some('\n') => {
+                    line += 1;
+                    break;
+                }
+                Some(c) => field.push(c),
+            }
+        }
+        if quoted {
+            error = Some("quoted field is never closed".to_string());
+        }
+        fields.push(field);
+
+        // 빈 줄은 건너뜀
+        if fields.len() == 1 && fields[0].trim().is_empty() && error.is_none() {
+            continue;
+        }
+        records.push(match error {
+            Some(reason) => Err(Rejected {
+                line: start_line,
+                reason,
+            }),
+            None => Ok(Record {
+                line: start_line,
+                fields,
+            }),
+        });
+    }
+    records
+}
+
+/// Column positions by header name, so columns can come in any order.
+pub struct Header(HashMap<String, usize>);
+
+impl Header {
+    /// Names are matched case-insensitively and without surrounding spaces.
+    pub fn new<S: AsRef<str>>(names: &[S]) -> Self {
+        Header(
+            names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| (name.as_ref().trim().to_lowercase(), i))
+                .collect(),
+        )
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// The trimmed value of column `name`; `None` when the column is missing
+    /// or the record is too short.
+    pub fn get<'a>(&self, record: &'a Record, name: &str) -> Option<&'a str> {
+        let i = *self.0.get(name)?;
+        record.fields.get(i).map(|f| f.trim())
+    }
+}
+
+/* ---------- Writing ---------- */
+
+/// Writes one record, quoting the fields that need it.
+pub fn write_record<S: AsRef<str>>(out: &mut impl Write, fields: &[S]) -> io::Result<()> {
+    let line: Vec<Cow<str>> = fields.iter().map(|f| quote(f.as_ref())).collect();
+    writeln!(out, "{}", line.join(","))
+}
+
+fn quote(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) || field.trim() != field {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(text: &str) -> Vec<Vec<String>> {
+        parse(text)
+            .into_iter()
+            .map(|r| r.map(|r| r.fields).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn reads_quoted_fields() {
+        let text = "a,\"b, c\",\"say \"\"hi\"\"\"\r\n\"two\nlines\",,x\n";
+        assert_eq!(
+            fields(text),
+            vec![
+                vec!["a", "b, c", "say \"hi\""],
+                vec!["two\nlines", "", "x"],
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_lines_across_quoted_line_breaks() {
+        let text = "\u{feff}date,note\n\n2025-03-04,\"one\ntwo\"\n2025-03-05,\"open\n";
+        let records = parse(text);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].as_ref().ok().map(|r| r.line), Some(1));
+        assert_eq!(records[1].as_ref().ok().map(|r| r.line), Some(3));
+        let rejected = records[2].as_ref().err().expect("unclosed quote");
+        assert_eq!(rejected.line, 5);
+        assert_eq!(rejected.reason, "quoted field is never closed");
+    }
+
+    #[test]
+    fn finds_columns_by_header_name() {
+        let records = parse("End , DATE,start\n18:00,2025-03-04,09:00\n2025-03-05\n");
+        let records: Vec<Record> = records.into_iter().map(|r| r.ok().unwrap()).collect();
+        let header = Header::new(&records[0].fields);
+        assert!(header.has("date") && !header.has("job"));
+        assert_eq!(header.get(&records[1], "date"), Some("2025-03-04"));
+        assert_eq!(header.get(&records[1], "start"), Some("09:00"));
+        assert_eq!(header.get(&records[1], "end"), Some("18:00"));
+        assert_eq!(header.get(&records[2], "start"), None);
+        assert_eq!(header.get(&records[1], "job"), None);
+    }
+
+    #[test]
+    fn writes_what_it_reads() {
+        let row = ["plain", "with, comma", "\"quoted\"", "two\nlines", " padded", ""];
+        let mut out = Vec::new();
+        write_record(&mut out, &row).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(
+            text,
+            "plain,\"with, comma\",\"\"\"quoted\"\"\",\"two\nlines\",\" padded\",\n"
+        );
+        assert_eq!(fields(&text), vec![row.to_vec()]);
+    }
+}
@@ -0,0 +1,421 @@
+use crate::holidays::HolidayCalendar;
+use crate::pay::{Job, JobId, PayRules, RateHistory};
+use crate::WorkEntry;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+/* ---------- Commands ---------- */
+
+/// A reversible change to the data. Every change goes through `History` so
+/// that it can be undone.
+#[derive(Clone)]
+pub enum Command {
+    /// Puts `entry` at `index` in the list of `date`.
+    InsertEntry {
+        date: NaiveDate,
+        index: usize,
+        entry: WorkEntry,
+    },
+    /// Takes the entry at `index` out of the list of `date`; `entry` is what
+    /// was there, kept for undo.
+    RemoveEntry {
+        date: NaiveDate,
+        index: usize,
+        entry: WorkEntry,
+    },
+    SetRates {
+        job: JobId,
+        before: RateHistory,
+        after: RateHistory,
+    },
+    SetRules {
+        job: JobId,
+        before: PayRules,
+        after: PayRules,
+    },
+    /// The name and colour of a job.
+    SetJobLabel {
+        job: JobId,
+        before: (String, [u8; 3]),
+        after: (String, [u8; 3]),
+    },
+    /// Puts `job` at `index` in the job list.
+    InsertJob { index: usize, job: Job },
+    /// Takes the job at `index` out of the list; `job` is kept for undo.
+    RemoveJob { index: usize, job: Job },
+    SetHolidays {
+        before: HolidayCalendar,
+        after: HolidayCalendar,
+    },
+    /// Replaces all entries, e.g. when a backup is restored.
+    SetEntries {
+        before: HashMap<NaiveDate, Vec<WorkEntry>>,
+        after: HashMap<NaiveDate, Vec<WorkEntry>>,
+    },
+    /// Several commands undone and redone as one step, e.g. an edit that
+    /// removes the old entry and inserts the new one.
+    Batch(Vec<Command>),
+}
+
+impl Command {
+    /// The command that undoes this one.
+    fn inverse(&self) -> Command {
+        match self.clone() {
+            Command::InsertEntry { date, index, entry } => {
+                Command::RemoveEntry { date, index, entry }
+            }
+            Command::RemoveEntry { date, index, entry } => {
+                Command::InsertEntry { date, index, entry }
+            }
+            Command::SetRates { job, before, after } => Command::SetRates {
+                job,
+                before: after,
+                after: before,
+            },
+            Command::SetRules { job, before, after } => Command::SetRules {
+                job,
+                before: after,
+                after: before,
+            },
+            Command::SetJobLabel { job, before, after } => Command::SetJobLabel {
+                job,
+                before: after,
+                after: before,
+            },
+            Command::InsertJob { index, job } => Command::RemoveJob { index, job },
+            Command::RemoveJob { index, job } => Command::InsertJob { index, job },
+            Command::SetHolidays { before, after } => Command::SetHolidays {
+                before: after,
+                after: before,
+            },
+            Command::SetEntries { before, after } => Command::SetEntries {
+                before: after,
+                after: before,
+            },
+            Command::Batch(list) => Command::Batch(list.iter().rev().map(Self::inverse).collect()),
+        }
+    }
+
+    /// Dates whose entries the command changes, for incremental saves.
+    pub fn dates(&self) -> Vec<NaiveDate> {
+        match self {
+            Command::InsertEntry { date, .. } | Command::RemoveEntry { date, .. } => vec![*date],
+            Command::SetRates { .. }
+            | Command::SetRules { .. }
+            | Command::SetJobLabel { .. }
+            | Command::InsertJob { .. }
+            | Command::RemoveJob { .. }
+            | Command::SetHolidays { .. } => Vec::new(),
+            Command::SetEntries { before, after } => {
+                before.keys().chain(after.keys()).copied().collect()
+            }
+            Command::Batch(list) => list.iter().flat_map(Self::dates).collect(),
+        }
+    }
+
+    /// Whether the command replaces all entries, alone or in a batch.
+    pub fn replaces_entries(&self) -> bool {
+        match self {
+            Command::SetEntries { .. } => true,
+            Command::Batch(list) => list.iter().any(Self::replaces_entries),
+            _ => false,
+        }
+    }
+
+    fn apply(
+        &self,
+        entries: &mut HashMap<NaiveDate, Vec<WorkEntry>>,
+        jobs: &mut Vec<Job>,
+        holidays: &mut HolidayCalendar,
+    ) {
+        match self {
+            Command::InsertEntry { date, index, entry } => {
+                let list = entries.entry(*date).or_default();
+                list.insert((*index).min(list.len()), entry.clone());
+            }
+            Command::RemoveEntry { date, index, .. } => {
+                if let Some(list) = entries.get_mut(date) {
+                    if *index < list.len() {
+                        list.remove(*index);
+                    }
+                    if list.is_empty() {
+                        entries.remove(date);
+                    }
+                }
+            }
+            Command::SetRates { job, after, .. } => {
+                if let Some(job) = jobs.iter_mut().find(|j| j.id == *job) {
+                    job.rates = after.clone();
+                }
+            }
+            Command::SetRules { job, after, .. } => {
+                if let Some(job) = jobs.iter_mut().find(|j| j.id == *job) {
+                    job.rules = after.clone();
+                }
+            }
+            Command::SetJobLabel { job, after, .. } => {
+                if let Some(job) = jobs.iter_mut().find(|j| j.id == *job) {
+                    (job.name, job.color) = after.clone();
+                }
+            }
+            Command::InsertJob { index, job } => {
+                jobs.insert((*index).min(jobs.len()), job.clone());
+            }
+            Command::RemoveJob { index, .. } => {
+                if *index < jobs.len() {
+                    jobs.remove(*index);
+                }
+            }
+            Command::SetHolidays { after, .. } => *holidays = after.clone(),
+            Command::SetEntries { after, .. } => *entries = after.clone(),
+            Command::Batch(list) => {
+                for command in list {
+                    command.apply(entries, jobs, holidays);
+                }
+            }
+        }
+    }
+}
+
+/* ---------- History ---------- */
+
+/// Undo and redo stacks. Kept in memory only, so the history lasts until
+/// the app is closed.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    /// Whether the last command may absorb the next one (see `merge`).
+    open: bool,
+}
+
+impl History {
+    /// Applies `command` and records it for undo.
+    pub fn execute(
+        &mut self,
+        command: Command,
+        entries: &mut HashMap<NaiveDate, Vec<WorkEntry>>,
+        jobs: &mut Vec<Job>,
+        holidays: &mut HolidayCalendar,
+    ) {
+        command.apply(entries, jobs, holidays);
+        self.record(command);
+    }
+
+    /// Records a change the UI has already made.
+    pub fn record(&mut self, command: Command) {
+        self.undo.push(command);
+        self.redo.clear();
+        self.open = false;
+    }
+
+    /// Records a change the UI has already made, such as a dragged rate.
+    /// Consecutive merged changes of the same job's rates, rules or label
+    /// become one undo step instead of one per frame, until `close`.
+    pub fn merge(&mut self, command: Command) {
+        let merged = match (self.undo.last_mut(), &command) {
+            (
+                Some(Command::SetRates { job, after, .. }),
+                Command::SetRates {
+                    job: new_job,
+                    after: new_after,
+                    ..
+                },
+            ) if self.open && job == new_job => {
+                *after = new_after.clone();
+                true
+            }
+            (
+                Some(Command::SetRules { job, after, .. }),
+                Command::SetRules {
+                    job: new_job,
+                    after: new_after,
+                    ..
+                },
+            ) if self.open && job == new_job => {
+                *after = new_after.clone();
+                true
+            }
+            (
+                Some(Command::SetJobLabel { job, after, .. }),
+                Command::SetJobLabel {
+                    job: new_job,
+                    after: new_after,
+                    ..
+                },
+            ) if self.open && job == new_job => {
+                *after = new_after.clone();
+                true
+            }
+            _ => false,
+        };
+        if !merged {
+            self.undo.push(command);
+        }
+        self.redo.clear();
+        self.open = true;
+    }
+
+    /// Ends the current merge group, e.g. when a drag or text edit is
+    /// finished, so the next change gets its own undo step.
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    /// Reverts the last command and returns it, or `None` when there is
+    /// nothing to undo.
+    pub fn undo(
+        &mut self,
+        entries: &mut HashMap<NaiveDate, Vec<WorkEntry>>,
+        jobs: &mut Vec<Job>,
+        holidays: &mut HolidayCalendar,
+    ) -> Option<&Command> {
+        let command = self.undo.pop()?;
+        command.inverse().apply(entries, jobs, holidays);
+        self.redo.push(command);
+        self.open = false;
+        self.redo.last()
+    }
+
+    /// Re-applies the last undone command and returns it.
+    pub fn redo(
+        &mut self,
+        entries: &mut HashMap<NaiveDate, Vec<WorkEntry>>,
+        jobs: &mut Vec<Job>,
+        holidays: &mut HolidayCalendar,
+    ) -> Option<&Command> {
+        let command = self.redo.pop()?;
+        command.apply(entries, jobs, holidays);
+        self.undo.push(command);
+        self.open = false;
+        self.undo.last()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undoes_deleting_a_job_and_moving_its_entries() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+        let start = date.and_hms_opt(9, 0, 0).unwrap();
+        let entry = WorkEntry {
+            start,
+            end: start + chrono::Duration::hours(8),
+            rate: None,
+            job: 1,
+            breaks: Vec::new(),
+        };
+        let mut entries = HashMap::from([(date, vec![entry.clone()])]);
+        let mut jobs = vec![Job::new(0, "Main"), Job::new(1, "Cafe")];
+        let mut holidays = HolidayCalendar::default();
+
+        let mut history = History::default();
+        let command = Command::Batch(vec![
+            Command::RemoveJob {
+                index: 1,
+                job: jobs[1].clone(),
+            },
+            Command::RemoveEntry {
+                date,
+                index: 0,
+                entry: entry.clone(),
+            },
+            Command::InsertEntry {
+                date,
+                index: 0,
+                entry: WorkEntry { job: 0, ..entry },
+            },
+        ]);
+        assert_eq!(command.dates(), vec![date, date]);
+        history.execute(command, &mut entries, &mut jobs, &mut holidays);
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(entries[&date][0].job, 0);
+
+        history.undo(&mut entries, &mut jobs, &mut holidays);
+        assert_eq!(jobs[1].name, "Cafe");
+        assert_eq!(entries[&date][0].job, 1);
+
+        history.redo(&mut entries, &mut jobs, &mut holidays);
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(entries[&date][0].job, 0);
+    }
+
+    #[test]
+    fn closing_a_merge_group_starts_a_new_undo_step() {
+        let mut entries = HashMap::new();
+        let mut jobs = vec![Job::new(0, "Main")];
+        let mut holidays = HolidayCalendar::default();
+        let multiplier = |m: f64| PayRules {
+            overtime_multiplier: m,
+            ..PayRules::default()
+        };
+        let set = |before: f64, after: f64| Command::SetRules {
+            job: 0,
+            before: multiplier(before),
+            after: multiplier(after),
+        };
+
+        // 한 번의 드래그 동안은 한 단계
+        let mut history = History::default();
+        for (before, after) in [(1.5, 1.6), (1.6, 1.7)] {
+            jobs[0].rules = multiplier(after);
+            history.merge(set(before, after));
+        }
+        history.close();
+        jobs[0].rules = multiplier(2.0);
+        history.merge(set(1.7, 2.0));
+
+        history.undo(&mut entries, &mut jobs, &mut holidays);
+        assert_eq!(jobs[0].rules.overtime_multiplier, 1.7);
+        history.undo(&mut entries, &mut jobs, &mut holidays);
+        assert_eq!(jobs[0].rules.overtime_multiplier, 1.5);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn undoing_a_restore_removes_the_jobs_it_added() {
+        let mut entries = HashMap::new();
+        let mut jobs = vec![Job::new(0, "Main")];
+        let mut holidays = HolidayCalendar::default();
+        let date = NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+        let start = date.and_hms_opt(9, 0, 0).unwrap();
+        let restored = HashMap::from([(
+            date,
+            vec![WorkEntry {
+                start,
+                end: start + chrono::Duration::hours(4),
+                rate: None,
+                job: 1,
+                breaks: Vec::new(),
+            }],
+        )]);
+
+        let mut history = History::default();
+        let command = Command::Batch(vec![
+            Command::InsertJob {
+                index: 1,
+                job: Job::new(1, "Cafe"),
+            },
+            Command::SetEntries {
+                before: entries.clone(),
+                after: restored,
+            },
+        ]);
+        assert!(command.replaces_entries());
+        history.execute(command, &mut entries, &mut jobs, &mut holidays);
+        assert_eq!((jobs.len(), entries.len()), (2, 1));
+
+        history.undo(&mut entries, &mut jobs, &mut holidays);
+        assert_eq!((jobs.len(), entries.len()), (1, 0));
+    }
+}
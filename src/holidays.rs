@@ -0,0 +1,393 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+/* ---------- Holiday calendar ---------- */
+
+/// Dates paid at the holiday rate: built-in public holidays plus days the
+/// user listed or imported.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HolidayCalendar {
+    /// Include South Korean public holidays (with lunar and substitute days).
+    pub korean_public_holidays: bool,
+    /// Holidays and days off listed or imported by the user, with a display name.
+    pub custom: BTreeMap<NaiveDate, String>,
+    #[serde(skip)]
+    builtin_cache: RefCell<HashMap<i32, Vec<(NaiveDate, &'static str)>>>,
+}
+
+impl PartialEq for HolidayCalendar {
+    // 내장 공휴일 캐시는 비교하지 않음
+    fn eq(&self, other: &Self) -> bool {
+        self.korean_public_holidays == other.korean_public_holidays && self.custom == other.custom
+    }
+}
+
+impl Default for HolidayCalendar {
+    fn default() -> Self {
+        Self {
+            korean_public_holidays: true,
+            custom: BTreeMap::new(),
+            builtin_cache: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+impl HolidayCalendar {
+    /// Display name of the holiday on `date`. User entries win over built-in ones.
+    pub fn name(&self, date: NaiveDate) -> Option<&str> {
+        if let Some(name) = self.custom.get(&date) {
+            return Some(name);
+        }
+        if self.korean_public_holidays {
+            return self.builtin_name(date);
+        }
+        None
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.name(date).is_some()
+    }
+
+    fn builtin_name(&self, date: NaiveDate) -> Option<&'static str> {
+        let mut cache = self.builtin_cache.borrow_mut();
+        cache
+            .entry(date.year())
+            .or_insert_with(|| korean_holidays(date.year()))
+            .iter()
+            .find(|(d, _)| *d == date)
+            .map(|(_, name)| *name)
+    }
+
+    /// Adds every holiday found in an iCalendar (`.ics`) or `date,name` CSV
+    /// file to the user list and returns how many dates were added.
+    pub fn import_file(&mut self, path: &str) -> Result<usize, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let days = if text.trim_start().starts_with("BEGIN:VCALENDAR") {
+            parse_ical(&text)
+        } else {
+            parse_holiday_csv(&text)?
+        };
+        if days.is_empty() {
+            return Err(format!("{}: no holidays found", path));
+        }
+        let count = days.len();
+        self.custom.extend(days);
+        Ok(count)
+    }
+}
+
+/* ---------- Import ---------- */
+
+/// All-day events from an iCalendar file. Multi-day events (exclusive
+/// `DTEND`) produce one entry per day.
+fn parse_ical(text: &str) -> Vec<(NaiveDate, String)> {
+    // RFC 5545 line unfolding: a leading space or tab continues the previous line.
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.lines() {
+        match raw.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+
+    let mut days = Vec::new();
+    let mut start: Option<NaiveDate> = None;
+    let mut end: Option<NaiveDate> = None;
+    let mut summary = String::new();
+    for line in &lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = key.split(';').next().unwrap_or("").to_ascii_uppercase();
+        match name.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VEVENT") => {
+                start = None;
+                end = None;
+                summary.clear();
+            }
+            "DTSTART" => start = parse_ical_date(value),
+            "DTEND" => end = parse_ical_date(value),
+            "SUMMARY" => {
+                summary = value
+                    .replace("\\,", ",")
+                    .replace("\\;", ";")
+                    .replace("\\n", " ")
+                    .replace("\\\\", "\\");
+            }
+            "END" if value.eq_ignore_ascii_case("VEVENT") => {
+                if let Some(first) = start {
+                    let last = end
+                        .filter(|e| *e > first)
+                        .map_or(first, |e| e - Duration::days(1));
+                    let label = if summary.trim().is_empty() {
+                        "Holiday".to_string()
+                    } else {
+                        summary.trim().to_string()
+                    };
+                    for date in first.iter_days().take_while(|d| *d <= last) {
+                        days.push((date, label.clone()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    days
+}
+
+fn parse_ical_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
+
+/// `YYYY-MM-DD,name` lines. A header line is skipped; other bad lines are errors.
+fn parse_holiday_csv(text: &str) -> Result<Vec<(NaiveDate, String)>, String> {
+    let mut days = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (date, name) = line.split_once(',').unwrap_or((line, ""));
+        match NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") {
+            Ok(date) => {
+                let name = name.trim().trim_matches('"');
+                let name = if name.is_empty() { "Holiday" } else { name };
+                days.push((date, name.to_string()));
+            }
+            Err(_) if i == 0 => continue,
+            Err(_) => return Err(format!("line {}: expected YYYY-MM-DD,name", i + 1)),
+        }
+    }
+    Ok(days)
+}
+
+/* ---------- Korean public holidays ---------- */
+
+#[derive(Clone, Copy, PartialEq)]
+enum Substitute {
+    Never,
+    /// Seollal and Chuseok: a day lost to a Sunday or another holiday.
+    SundayOrOverlap,
+    /// Since 2014/2021/2023 depending on the holiday: lost to a weekend or another holiday.
+    WeekendOrOverlap,
+}
+
+struct BaseHoliday {
+    date: NaiveDate,
+    name: &'static str,
+    substitute: Substitute,
+    /// Last day of the holiday run the date belongs to (Seollal/Chuseok span three days).
+    span_end: NaiveDate,
+}
+
+/// Public holidays of South Korea for `year`, including substitute holidays
+/// (대체공휴일). Election days and one-off temporary holidays are not included.
+fn korean_holidays(year: i32) -> Vec<(NaiveDate, &'static str)> {
+    let ymd = |m, d| NaiveDate::from_ymd_opt(year, m, d).unwrap();
+    let weekend_rule = |since: i32| {
+        if year >= since {
+            Substitute::WeekendOrOverlap
+        } else {
+            Substitute::Never
+        }
+    };
+    let lunar_rule = if year >= 2014 {
+        Substitute::SundayOrOverlap
+    } else {
+        Substitute::Never
+    };
+
+    let mut base: Vec<BaseHoliday> = Vec::new();
+    let mut single = |date: NaiveDate, name, substitute| {
+        base.push(BaseHoliday {
+            date,
+            name,
+            substitute,
+            span_end: date,
+        })
+    };
+    single(ymd(1, 1), "New Year's Day", Substitute::Never);
+    single(ymd(3, 1), "Independence Movement Day", weekend_rule(2021));
+    single(ymd(5, 5), "Children's Day", weekend_rule(2014));
+    single(ymd(6, 6), "Memorial Day", Substitute::Never);
+    single(ymd(8, 15), "Liberation Day", weekend_rule(2021));
+    single(ymd(10, 3), "National Foundation Day", weekend_rule(2021));
+    single(ymd(10, 9), "Hangul Day", weekend_rule(2021));
+    single(ymd(12, 25), "Christmas Day", weekend_rule(2023));
+    single(buddhas_birthday(year), "Buddha's Birthday", weekend_rule(2023));
+
+    for (day, name) in [(seollal(year), "Seollal"), (chuseok(year), "Chuseok")] {
+        let span_end = day + Duration::days(1);
+        for offset in -1..=1 {
+            base.push(BaseHoliday {
+                date: day + Duration::days(offset),
+                name,
+                substitute: lunar_rule,
+                span_end,
+            });
+        }
+    }
+    base.sort_by_key(|h| h.date);
+
+    // Each lost day earns one substitute, placed after the holiday run on the
+    // first weekday that is not already a holiday.
+    let mut substitutes: Vec<NaiveDate> = Vec::new();
+    let mut handled: Vec<NaiveDate> = Vec::new();
+    for h in &base {
+        if h.substitute == Substitute::Never || handled.contains(&h.date) {
+            continue;
+        }
+        let overlaps = base.iter().filter(|o| o.date == h.date).count() > 1;
+        let lost = overlaps
+            || match h.substitute {
+                Substitute::SundayOrOverlap => h.date.weekday() == Weekday::Sun,
+                Substitute::WeekendOrOverlap => {
+                    matches!(h.date.weekday(), Weekday::Sat | Weekday::Sun)
+                }
+                Substitute::Never => false,
+            };
+        if !lost {
+            continue;
+        }
+        handled.push(h.date);
+        let mut candidate = h.span_end + Duration::days(1);
+        while matches!(candidate.weekday(), Weekday::Sat | Weekday::Sun)
+            || base.iter().any(|o| o.date == candidate)
+            || substitutes.contains(&candidate)
+        {
+            candidate += Duration::days(1);
+        }
+        substitutes.push(candidate);
+    }
+
+    let mut out: Vec<(NaiveDate, &'static str)> = Vec::new();
+    for h in &base {
+        if !out.iter().any(|(d, _)| *d == h.date) {
+            out.push((h.date, h.name));
+        }
+    }
+    out.extend(substitutes.into_iter().map(|d| (d, "Substitute Holiday")));
+    out.sort_by_key(|(d, _)| *d);
+    out
+}
+
+/* ---------- Lunar calendar ---------- */
+
+// Korean lunar dates follow the Chinese rules evaluated in KST (UTC+9): a
+// lunar month starts on the local date of a new moon, and month N is the one
+// containing principal solar term N. We only need three months, so compute
+// them astronomically (Meeus, "Astronomical Algorithms", ch. 25 and 49)
+// instead of carrying a lookup table. Accurate to a few minutes, which only
+// matters when a new moon falls right at local midnight.
+
+/// 1st day of lunar month 1; the month containing Yushui (solar longitude 330°).
+fn seollal(year: i32) -> NaiveDate {
+    lunar_month_start(solar_term(year, 330.0))
+}
+
+/// 8th day of lunar month 4; the month containing Xiaoman (60°).
+fn buddhas_birthday(year: i32) -> NaiveDate {
+    lunar_month_start(solar_term(year, 60.0)) + Duration::days(7)
+}
+
+/// 15th day of lunar month 8; the month containing the autumn equinox (180°).
+fn chuseok(year: i32) -> NaiveDate {
+    lunar_month_start(solar_term(year, 180.0)) + Duration::days(14)
+}
+
+const KST_OFFSET_DAYS: f64 = 9.0 / 24.0;
+const SYNODIC_MONTH: f64 = 29.530588861;
+
+fn kst_date(jd: f64) -> NaiveDate {
+    let days = (jd + KST_OFFSET_DAYS - 2440587.5).floor() as i64;
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap() + Duration::days(days)
+}
+
+/// KST date of the new moon that starts the lunar month containing `term_jd`.
+fn lunar_month_start(term_jd: f64) -> NaiveDate {
+    let term_date = kst_date(term_jd);
+    let mut k = ((term_jd - 2451550.09766) / SYNODIC_MONTH).floor();
+    while kst_date(new_moon(k)) > term_date {
+        k -= 1.0;
+    }
+    while kst_date(new_moon(k + 1.0)) <= term_date {
+        k += 1.0;
+    }
+    kst_date(new_moon(k))
+}
+
+/// Julian day (UT, approximately) of new moon number `k` counted from January 2000.
+fn new_moon(k: f64) -> f64 {
+    let t = k / 1236.85;
+    let jde = 2451550.09766 + SYNODIC_MONTH * k + 0.00015437 * t * t - 0.000000150 * t.powi(3)
+        + 0.00000000073 * t.powi(4);
+    let e = 1.0 - 0.002516 * t - 0.0000074 * t * t;
+    let m = (2.5534 + 29.10535670 * k - 0.0000014 * t * t - 0.00000011 * t.powi(3)).to_radians();
+    let mp = (201.5643 + 385.81693528 * k + 0.0107582 * t * t + 0.00001238 * t.powi(3)
+        - 0.000000058 * t.powi(4))
+    .to_radians();
+    let f = (160.7108 + 390.67050284 * k - 0.0016118 * t * t - 0.00000227 * t.powi(3)
+        + 0.000000011 * t.powi(4))
+    .to_radians();
+    let om = (124.7746 - 1.56375588 * k + 0.0020672 * t * t + 0.00000215 * t.powi(3)).to_radians();
+
+    let correction = -0.40720 * mp.sin()
+        + 0.17241 * e * m.sin()
+        + 0.01608 * (2.0 * mp).sin()
+        + 0.01039 * (2.0 * f).sin()
+        + 0.00739 * e * (mp - m).sin()
+        - 0.00514 * e * (mp + m).sin()
+        + 0.00208 * e * e * (2.0 * m).sin()
+        - 0.00111 * (mp - 2.0 * f).sin()
+        - 0.00057 * (mp + 2.0 * f).sin()
+        + 0.00056 * e * (2.0 * mp + m).sin()
+        - 0.00042 * (3.0 * mp).sin()
+        + 0.00042 * e * (m + 2.0 * f).sin()
+        + 0.00038 * e * (m - 2.0 * f).sin()
+        - 0.00024 * e * (2.0 * mp - m).sin()
+        - 0.00017 * om.sin()
+        - 0.00007 * (mp + 2.0 * m).sin()
+        + 0.00004 * (2.0 * mp - 2.0 * f).sin()
+        + 0.00004 * (3.0 * m).sin()
+        + 0.00003 * (mp + m - 2.0 * f).sin()
+        + 0.00003 * (2.0 * mp + 2.0 * f).sin()
+        - 0.00003 * (mp + m + 2.0 * f).sin()
+        + 0.00003 * (mp - m + 2.0 * f).sin()
+        - 0.00002 * (mp - m - 2.0 * f).sin()
+        - 0.00002 * (3.0 * mp + m).sin()
+        + 0.00002 * (4.0 * mp).sin();
+
+    // JDE is terrestrial time; ΔT is roughly 70 seconds this century.
+    jde + correction - 70.0 / 86400.0
+}
+
+/// Apparent solar longitude in degrees at Julian day `jd`.
+fn solar_longitude(jd: f64) -> f64 {
+    let t = (jd - 2451545.0) / 36525.0;
+    let l0 = 280.46646 + 36000.76983 * t + 0.0003032 * t * t;
+    let m = (357.52911 + 35999.05029 * t - 0.0001537 * t * t).to_radians();
+    let c = (1.914602 - 0.004817 * t - 0.000014 * t * t) * m.sin()
+        + (0.019993 - 0.000101 * t) * (2.0 * m).sin()
+        + 0.000289 * (3.0 * m).sin();
+    let om = (125.04 - 1934.136 * t).to_radians();
+    (l0 + c - 0.00569 - 0.00478 * om.sin()).rem_euclid(360.0)
+}
+
+/// Julian day in `year` when the sun reaches `longitude` degrees.
+fn solar_term(year: i32, longitude: f64) -> f64 {
+    // Longitude 0° is the March equinox (~day 80); the sun moves ~0.9856°/day.
+    let march_equinox = 2451623.8 + 365.2422 * (year - 2000) as f64;
+    let mut jd = march_equinox + longitude / 360.0 * 365.2422;
+    if longitude >= 270.0 {
+        // Terms from the winter solstice on that fall early in the calendar year.
+        jd -= 365.2422;
+    }
+    for _ in 0..6 {
+        let diff = (longitude - solar_longitude(jd) + 540.0).rem_euclid(360.0) - 180.0;
+        jd += diff * 365.2422 / 360.0;
+    }
+    jd
+}
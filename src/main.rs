@@ -1,27 +1,312 @@
-use chrono::{Datelike, Local, NaiveDate, NaiveTime, Timelike};
+mod backup;
+mod cli;
+mod csv;
+mod history;
+mod holidays;
+mod money;
+mod pay;
+mod storage;
+mod times;
+mod workspace;
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
 use eframe::egui;
-use std::collections::HashMap;
-use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use history::{Command, History};
+use holidays::HolidayCalendar;
+use money::{Currency, Money, RoundingPolicy};
+use pay::{
+    calculate_ledger, calculate_pay_summary, calculate_weekly_allowances, find_job,
+    priced, priced_on, total_pay, BreakRule, DayAttribution, Job, JobId, OvertimeMode, PayLedger,
+    PayRules, PunchRounding, RateHistory, WeeklyAllowances,
+};
+use serde::{Deserialize, Serialize};
+use storage::{CsvStore, Format, LoadReport, Snapshot, Storage};
+use times::{hhmm, hhmm_on, parse_time, EntryError};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration as StdDuration, Instant};
+use workspace::Workspace;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct WorkEntry {
+    /// Clock-in and clock-out; the end may fall on the next day.
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    /// Hourly rate for this entry only; `None` follows the rate history.
+    rate: Option<f64>,
+    job: JobId,
+    /// Breaks logged within the shift.
+    breaks: Vec<Break>,
+}
+
+impl WorkEntry {
+    /// Whether the two shifts share any time.
+    fn overlaps(&self, other: &WorkEntry) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Break {
+    start: NaiveTime,
+    end: NaiveTime,
+    paid: bool,
+}
+
+/// A break as typed into the entry form, before it is validated.
+#[derive(Default)]
+struct BreakInput {
+    start: String,
+    end: String,
+    paid: bool,
+}
+
+impl Break {
+    /// Whether the break lies within the shift from `start` to `end`. Like
+    /// the pay engine, the break starts at the first such time after `start`.
+    fn within(&self, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+        let mut from = start.date().and_time(self.start);
+        if from < start {
+            from += Duration::days(1);
+        }
+        let mut to = from.date().and_time(self.end);
+        if to <= from {
+            to += Duration::days(1);
+        }
+        to <= end
+    }
+}
+
+impl BreakInput {
+    /// Validates the break shown as number `n` of the shift from `start` to
+    /// `end`.
+    fn parse(
+        &self,
+        n: usize,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Break, EntryError> {
+        let b = Break {
+            start: parse_time(&self.start).map_err(|e| EntryError::BreakStart(n, e))?,
+            end: parse_time(&self.end).map_err(|e| EntryError::BreakEnd(n, e))?,
+            paid: self.paid,
+        };
+        if b.start == b.end {
+            return Err(EntryError::ZeroBreak(n));
+        }
+        if !b.within(start, end) {
+            return Err(EntryError::BreakOutsideShift(n));
+        }
+        Ok(b)
+    }
+}
+
+/// Times and rate typed into an entry form, before they are validated.
+#[derive(Default)]
+struct EntryForm {
     start: String,
     end: String,
+    end_next_day: bool,
+    breaks: Vec<BreakInput>,
+    rate_override: bool,
+    rate: f64,
+}
+
+impl EntryForm {
+    /// The form filled in with an existing entry.
+    fn from_entry(entry: &WorkEntry) -> Self {
+        Self {
+            start: hhmm(entry.start.time()),
+            end: hhmm(entry.end.time()),
+            end_next_day: entry.end.date() > entry.start.date(),
+            breaks: entry
+                .breaks
+                .iter()
+                .map(|b| BreakInput {
+                    start: hhmm(b.start),
+                    end: hhmm(b.end),
+                    paid: b.paid,
+                })
+                .collect(),
+            rate_override: entry.rate.is_some(),
+            rate: entry.rate.unwrap_or_default(),
+        }
+    }
+}
+
+/// What the file dialog does once confirmed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FileAction {
+    Open,
+    New,
+    SaveAs,
+    NewProfile,
+}
+
+/// The window asking for a file path or a profile name.
+struct FileDialog {
+    action: FileAction,
+    text: String,
+    error: Option<String>,
+}
+
+/// An entry being changed in the popup list, or a copy of one.
+struct EntryEdit {
+    /// Date and position of the entry in its list.
+    from: NaiveDate,
+    index: usize,
+    /// Save as a new entry instead of replacing the original.
+    duplicate: bool,
+    job: JobId,
+    /// Date the entry is saved on; changing it moves the entry.
+    date: NaiveDate,
+    form: EntryForm,
+    /// Length in minutes of the long shift the user was warned about; a
+    /// different length asks again.
+    long_confirmed: Option<i64>,
+    error: Option<String>,
 }
 
 struct App {
     month_first: NaiveDate,
     selected_date: Option<NaiveDate>,
-    global_rate: f64,
+    jobs: Vec<Job>,
+    /// Job that new entries go to and that the rate and rule controls edit.
+    active_job: JobId,
+    new_rate: f64,
+    new_rate_from: NaiveDate,
+    currency: Currency,
+    holidays: HolidayCalendar,
     entries: HashMap<NaiveDate, Vec<WorkEntry>>,
+    history: History,
     show_popup: bool,
-    temp_start: String,
-    temp_end: String,
-    csv_path: String,
+    new_entry: EntryForm,
+    editing: Option<EntryEdit>,
+    storage: Box<dyn Storage>,
+    /// Set until a data file was opened, so that a file that failed to load
+    /// is never saved over.
+    read_only: bool,
     popup_error: Option<String>,
+    /// Entry longer than `max_shift_hours`, waiting for confirmation.
+    pending_long_entry: Option<WorkEntry>,
+    /// Shifts longer than this ask before they are saved.
+    max_shift_hours: f64,
+    show_rules: bool,
+    new_holiday_date: NaiveDate,
+    new_holiday_name: String,
+    holiday_import_path: String,
+    holiday_import_msg: Option<String>,
+    load_report: Option<LoadReport>,
+    /// Dates whose entries changed since the last save or load.
+    changed: HashSet<NaiveDate>,
+    /// The settings as of the last save or load.
+    saved_settings: Settings,
+    error_toast: Option<String>,
+    show_close_prompt: bool,
+    /// Set once the user chose to close despite unsaved changes.
+    allow_close: bool,
+    autosave: bool,
+    /// Backups of the data file to keep.
+    backup_count: usize,
+    /// When the data first differed from the saved files.
+    dirty_since: Option<Instant>,
+    last_backup: Option<Instant>,
+    show_backups: bool,
+    workspace: Workspace,
+    file_dialog: Option<FileDialog>,
+    /// Window title as last set.
+    title: String,
+    theme: egui::ThemePreference,
+    show_settings: bool,
+}
+
+/// Pay summed over the shown month and over all data. The weekly holiday
+/// allowance is kept apart from pay for hours worked.
+#[derive(Default)]
+struct Totals {
+    month: Money,
+    overall: Money,
+    month_allowance: Money,
+    overall_allowance: Money,
+}
+
+/// Everything besides the entries: part of the native file, or stored next
+/// to a CSV file or database.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    jobs: Vec<Job>,
+    active_job: JobId,
+    currency: Currency,
+    holidays: HolidayCalendar,
+    #[serde(default = "default_max_shift_hours")]
+    max_shift_hours: f64,
+    #[serde(default = "default_true")]
+    autosave: bool,
+    #[serde(default = "default_backup_count")]
+    backup_count: usize,
+    /// Single-job settings written before jobs existed; read only.
+    #[serde(skip_serializing)]
+    rates: Option<RateHistory>,
+    #[serde(skip_serializing)]
+    pay_rules: Option<PayRules>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            jobs: Vec::new(),
+            active_job: 0,
+            currency: Currency::default(),
+            holidays: HolidayCalendar::default(),
+            max_shift_hours: default_max_shift_hours(),
+            autosave: true,
+            backup_count: default_backup_count(),
+            rates: None,
+            pay_rules: None,
+        }
+    }
+}
+
+fn default_max_shift_hours() -> f64 {
+    14.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_backup_count() -> usize {
+    10
 }
 
+/// Preferences of this installation rather than of a data file. eframe
+/// keeps them, along with the window size and position, between runs.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct UiPrefs {
+    theme: egui::ThemePreference,
+    /// First day of the month last shown.
+    month: Option<NaiveDate>,
+}
+
+impl Default for UiPrefs {
+    fn default() -> Self {
+        Self {
+            theme: egui::ThemePreference::System,
+            month: None,
+        }
+    }
+}
+
+const UI_PREFS_KEY: &str = "ui_prefs";
+
+/// How long after a change the data is saved automatically.
+const AUTOSAVE_DELAY: StdDuration = StdDuration::from_secs(5);
+/// Autosaves take a backup at most this often; manual saves always do.
+const BACKUP_INTERVAL: StdDuration = StdDuration::from_secs(10 * 60);
+
 impl Default for App {
     fn default() -> Self {
         let today = Local::now().naive_local().date();
@@ -29,50 +314,243 @@ impl Default for App {
         let mut app = Self {
             month_first: first,
             selected_date: None,
-            global_rate: 30.0,
+            jobs: vec![Job::default()],
+            active_job: 0,
+            new_rate: 0.0,
+            new_rate_from: today,
+            currency: Currency::default(),
+            holidays: HolidayCalendar::default(),
             entries: HashMap::new(),
+            history: History::default(),
             show_popup: false,
-            temp_start: "".into(),
-            temp_end: "".into(),
-            csv_path: "work_data.csv".into(),
+            new_entry: EntryForm::default(),
+            editing: None,
+            storage: Box::new(CsvStore::new(Path::new("work_data.csv"))),
+            read_only: true,
             popup_error: None,
+            pending_long_entry: None,
+            max_shift_hours: default_max_shift_hours(),
+            show_rules: false,
+            new_holiday_date: today,
+            new_holiday_name: "".into(),
+            holiday_import_path: "".into(),
+            holiday_import_msg: None,
+            load_report: None,
+            changed: HashSet::new(),
+            saved_settings: Settings::default(),
+            error_toast: None,
+            show_close_prompt: false,
+            allow_close: false,
+            autosave: true,
+            backup_count: default_backup_count(),
+            dirty_since: None,
+            last_backup: None,
+            show_backups: false,
+            workspace: Workspace::load(),
+            file_dialog: None,
+            title: String::new(),
+            theme: egui::ThemePreference::System,
+            show_settings: false,
         };
-        app.load_csv();
+        app.saved_settings = app.settings();
+        app.new_rate = app.job().rates.rate_on(today);
+        app.new_entry.rate = app.new_rate;
+        app
+    }
+}
+
+impl App {
+    fn new(cc: &eframe::CreationContext) -> Self {
+        let mut app = Self::default();
+        let path = app.workspace.start_file();
+        if let Err(e) = app.open_file(&path) {
+            app.error_toast = Some(e);
+        }
+        app.new_entry.rate = app.new_rate;
+        let prefs: UiPrefs = cc
+            .storage
+            .and_then(|s| eframe::get_value(s, UI_PREFS_KEY))
+            .unwrap_or_default();
+        app.theme = prefs.theme;
+        if let Some(month) = prefs.month {
+            app.month_first = month;
+        }
+        cc.egui_ctx.set_theme(app.theme);
+        if let Some(e) = &app.workspace.load_error {
+            app.error_toast = Some(format!(
+                "Could not read {}. Profiles and recent files are not saved until it is fixed.",
+                e
+            ));
+        }
         app
     }
 }
 
 impl eframe::App for App {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let prefs = UiPrefs {
+            theme: self.theme,
+            month: Some(self.month_first),
+        };
+        eframe::set_value(storage, UI_PREFS_KEY, &prefs);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _f: &mut eframe::Frame) {
+        // 창 제목에 파일 이름과 프로필 표시
+        let file = self.storage.path().file_name().unwrap_or_default();
+        let title = format!(
+            "Money Calendar — {} ({})",
+            file.to_string_lossy(),
+            self.workspace.current
+        );
+        if title != self.title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.title = title;
+        }
         // 단축키로 저장
         if (ctx.input(|i| i.modifiers.command) || ctx.input(|i| i.modifiers.ctrl))
             && ctx.input(|i| i.key_pressed(egui::Key::S))
         {
-            self.save_csv();
+            self.save_data();
+        }
+        // 저장하지 않은 변경이 있으면 닫기 전에 확인
+        if ctx.input(|i| i.viewport().close_requested()) && self.is_dirty() && !self.allow_close {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.show_close_prompt = true;
+        }
+        // 변경 후 잠시 기다렸다가 자동 저장, 실패하면 다시 기다림
+        if self.autosave && !self.read_only && self.is_dirty() {
+            let since = *self.dirty_since.get_or_insert_with(Instant::now);
+            let wait = AUTOSAVE_DELAY.saturating_sub(since.elapsed());
+            if wait.is_zero() {
+                let backup = self.last_backup.is_none_or(|t| t.elapsed() >= BACKUP_INTERVAL);
+                self.dirty_since = (!self.save(backup)).then(Instant::now);
+            } else {
+                ctx.request_repaint_after(wait);
+            }
+        } else {
+            self.dirty_since = None;
+        }
+        // 실행 취소 / 다시 실행 (입력 중인 텍스트 칸은 egui가 처리)
+        if (ctx.input(|i| i.modifiers.command) || ctx.input(|i| i.modifiers.ctrl))
+            && ctx.input(|i| i.key_pressed(egui::Key::Z))
+            && !ctx.wants_keyboard_input()
+        {
+            if ctx.input(|i| i.modifiers.shift) {
+                self.redo();
+            } else {
+                self.undo();
+            }
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
-                ui.label("Hourly (global):");
+                self.file_menu_ui(ui);
+                if job_picker(ui, "header_job", &self.jobs, &mut self.active_job) {
+                    self.new_rate = self.job().rates.rate_on(self.new_rate_from);
+                }
+                ui.label("Hourly:");
                 ui.add(
-                    egui::DragValue::new(&mut self.global_rate)
-                        .clamp_range(0.0..=1_000_000.0)
-                        .suffix(" $"),
+                    egui::DragValue::new(&mut self.new_rate)
+                        .range(0.0..=1_000_000.0)
+                        .suffix(format!(" {}", self.currency.symbol())),
                 );
+                ui.label("from");
+                ui.add(
+                    egui_extras::DatePickerButton::new(&mut self.new_rate_from)
+                        .id_salt("new_rate_from"),
+                );
+                if ui.button("Apply").clicked() {
+                    let before = self.job().rates.clone();
+                    let mut after = before.clone();
+                    after.changes.insert(self.new_rate_from, self.new_rate);
+                    self.execute(Command::SetRates {
+                        job: self.job().id,
+                        before,
+                        after,
+                    });
+                }
+                let today = Local::now().naive_local().date();
+                let rate = self.job().rates.rate_on(today);
+                ui.small(format!("(today: {})", self.rate_text(rate)));
                 if ui.button("💾 Save (⌘/Ctrl+S)").clicked() {
-                    self.save_csv();
+                    self.save_data();
+                }
+                if self.is_dirty() {
+                    ui.colored_label(egui::Color32::from_rgb(200, 120, 0), "● Unsaved changes");
+                }
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("↶"))
+                    .on_hover_text("Undo (⌘/Ctrl+Z)")
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("↷"))
+                    .on_hover_text("Redo (⌘/Ctrl+Shift+Z)")
+                    .clicked()
+                {
+                    self.redo();
+                }
+                if ui.button("⚙ Jobs & Pay Rules").clicked() {
+                    self.show_rules = !self.show_rules;
+                }
+                if ui.button("🔧 Settings").clicked() {
+                    self.show_settings = !self.show_settings;
                 }
             });
 
+            let ledger = calculate_ledger(&self.entries, &self.jobs, &self.holidays, self.currency);
+            let allowances = calculate_weekly_allowances(
+                &self.entries,
+                &ledger,
+                &self.jobs,
+                &self.holidays,
+                self.currency,
+            );
+
             ui.separator();
-            self.calendar_ui(ui);
+            self.calendar_ui(ui, &ledger, &allowances);
             ui.separator();
 
-            let (month_total, overall_total) = self.compute_totals();
+            let (totals, job_totals) = self.compute_totals(&ledger, &allowances);
+            let c = self.currency;
             ui.heading(format!(
-                "📅 This Month: ${:.2}    💰 Overall: ${:.2}",
-                month_total, overall_total
+                "📅 This Month: {}    💰 Overall: {}",
+                c.format(totals.month),
+                c.format(totals.overall)
             ));
+            if self.jobs.iter().any(|j| j.rules.weekly_allowance_enabled) {
+                ui.heading(format!(
+                    "🎁 Weekly allowance (주휴수당): {}    Overall: {}",
+                    c.format(totals.month_allowance),
+                    c.format(totals.overall_allowance)
+                ));
+            }
+            // 직장별 합계
+            if self.jobs.len() > 1 {
+                let no_totals = Totals::default();
+                for job in &self.jobs {
+                    let t = job_totals.get(&job.id).unwrap_or(&no_totals);
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new("■").color(job_color(job)));
+                        ui.strong(&job.name);
+                        ui.label(format!(
+                            "This Month: {}    Overall: {}",
+                            c.format(t.month),
+                            c.format(t.overall)
+                        ));
+                        if job.rules.weekly_allowance_enabled {
+                            ui.small(format!(
+                                "(🎁 +{} / +{})",
+                                c.format(t.month_allowance),
+                                c.format(t.overall_allowance)
+                            ));
+                        }
+                    });
+                }
+            }
 
             if self.show_popup {
                 if let Some(date) = self.selected_date {
@@ -81,81 +559,119 @@ impl eframe::App for App {
                         .resizable(false)
                         .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                         .show(ctx, |ui| {
-                            ui.label("Add a work entry (HH:MM, 24h)");
+                            ui.label("Add a work entry (09:05, 0905, 9am, 21h…)");
                             ui.horizontal(|ui| {
-                                ui.label("Start");
-                                ui.text_edit_singleline(&mut self.temp_start);
-                                ui.label("End");
-                                ui.text_edit_singleline(&mut self.temp_end);
+                                ui.label("Job");
+                                job_picker(ui, "popup_job", &self.jobs, &mut self.active_job);
                             });
-                            ui.small("Lunch break (30m) is auto-deducted. After 15:30 → 1.5× overtime.");
+                            let rate_hint = format!(
+                                "(rate on this date: {})",
+                                self.rate_text(self.job().rates.rate_on(date))
+                            );
+                            entry_form_ui(ui, &mut self.new_entry, self.currency, &rate_hint);
+                            ui.small(format!(
+                                "{} {}",
+                                self.job().rules.break_hint(),
+                                self.job().rules.overtime_hint()
+                            ));
+                            let rules = &self.job().rules;
+                            if rules.punch_rounding != PunchRounding::Off {
+                                ui.small(format!(
+                                    "Punches are rounded: {}{}.",
+                                    rules.punch_rounding.label(),
+                                    if rules.punch_rounding.uses_step() {
+                                        format!(" ({} min)", rules.punch_rounding_step)
+                                    } else {
+                                        String::new()
+                                    }
+                                ));
+                            }
 
                             if let Some(err) = &self.popup_error {
                                 ui.colored_label(egui::Color32::from_rgb(190, 40, 40), err);
                             }
 
-                            ui.horizontal(|ui| {
-                                if ui.button("➕ Save Entry").clicked() {
-                                    if let Some(_) = calculate_pay_summary(
-                                        &self.temp_start,
-                                        &self.temp_end,
-                                        self.global_rate,
-                                    ) {
-                                        self.entries
-                                            .entry(date)
-                                            .or_default()
-                                            .push(WorkEntry {
-                                                start: self.temp_start.clone(),
-                                                end: self.temp_end.clone(),
-                                            });
-                                        self.temp_start.clear();
-                                        self.temp_end.clear();
+                            if let Some(entry) = &self.pending_long_entry {
+                                let hours = (entry.end - entry.start).num_minutes() as f64 / 60.0;
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(200, 120, 0),
+                                    format!(
+                                        "⚠ This shift is {:.1}h, longer than {:.0}h. Save anyway?",
+                                        hours, self.max_shift_hours
+                                    ),
+                                );
+                                ui.horizontal(|ui| {
+                                    if ui.button("Save anyway").clicked() {
+                                        if let Some(entry) = self.pending_long_entry.take() {
+                                            self.add_entry(date, entry);
+                                        }
+                                    }
+                                    if ui.button("Cancel").clicked() {
+                                        self.pending_long_entry = None;
+                                    }
+                                });
+                            } else {
+                                ui.horizontal(|ui| {
+                                    if ui.button("➕ Save Entry").clicked() {
+                                        let entry = self.entry_from_form(
+                                            &self.new_entry,
+                                            date,
+                                            self.active_job,
+                                            None,
+                                        );
+                                        match entry {
+                                            Ok(entry) => {
+                                                let length = entry.end - entry.start;
+                                                let max = self.max_shift_hours * 60.0;
+                                                if length.num_minutes() as f64 > max {
+                                                    // 09:00–08:00 같은 오타일 수 있으니 확인
+                                                    self.pending_long_entry = Some(entry);
+                                                } else {
+                                                    self.add_entry(date, entry);
+                                                }
+                                            }
+                                            Err(err) => self.popup_error = Some(err.to_string()),
+                                        }
+                                    }
+                                    if ui.button("Close").clicked() {
+                                        self.show_popup = false;
                                         self.popup_error = None;
-                                    } else {
-                                        self.popup_error =
-                                            Some("Check time format (HH:MM) and duration.".into());
+                                        self.editing = None;
                                     }
-                                }
-                                if ui.button("Close").clicked() {
-                                    self.show_popup = false;
-                                    self.popup_error = None;
-                                }
-                            });
+                                });
+                            }
 
                             ui.separator();
                             ui.label("Entries on this date:");
-                            if let Some(list) = self.entries.get_mut(&date) {
-                                let mut remove_idx: Option<usize> = None;
-                                for (i, e) in list.iter().enumerate() {
-                                    ui.horizontal(|ui| {
-                                        ui.label(format!("{}.", i + 1));
-                                        ui.monospace(format!("{} - {}", e.start, e.end));
-                                        if let Some(summary) = calculate_pay_summary(
-                                            &e.start,
-                                            &e.end,
-                                            self.global_rate,
-                                        ) {
-                                            ui.small(format!(
-                                                "{:.2}h reg + {:.2}h OT → ${:.2}",
-                                                summary.regular_hours,
-                                                summary.overtime_hours,
-                                                summary.total_pay
-                                            ));
-                                        } else {
-                                            ui.small("Invalid times");
-                                        }
-                                        if ui.button("🗑").clicked() {
-                                            remove_idx = Some(i);
-                                        }
-                                    });
-                                }
-                                if let Some(i) = remove_idx {
-                                    list.remove(i);
-                                }
-                            }
+                            self.entry_list_ui(ui, date, &ledger);
                         });
                 }
             }
+
+            if self.show_rules {
+                self.pay_rules_ui(ctx);
+            }
+
+            if self.show_settings {
+                self.settings_ui(ctx);
+            }
+
+            if self.load_report.as_ref().is_some_and(|r| !r.rejected.is_empty()) {
+                self.load_report_ui(ctx);
+            }
+
+            if self.show_backups {
+                self.backups_ui(ctx);
+            }
+
+            if self.file_dialog.is_some() {
+                self.file_dialog_ui(ctx);
+            }
+
+            if self.show_close_prompt {
+                self.close_prompt_ui(ctx);
+            }
+            self.error_toast_ui(ctx);
         });
     }
 }
@@ -163,7 +679,12 @@ impl eframe::App for App {
 /* ---------- Calendar UI ---------- */
 
 impl App {
-    fn calendar_ui(&mut self, ui: &mut egui::Ui) {
+    fn calendar_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        ledger: &PayLedger,
+        allowances: &WeeklyAllowances,
+    ) {
         let y = self.month_first.year();
         let m = self.month_first.month();
         let today = Local::now().naive_local().date();
@@ -188,21 +709,25 @@ impl App {
     
         ui.add_space(6.0);
     
-        // 🗓 요일 헤더
-        let weekdays = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+        // 🗓 요일 헤더 (선택한 직장의 급여 주 시작 요일부터)
+        let week_rules = self.job().rules.clone();
+        let week_start = week_rules.week_start;
+        let weekdays: Vec<Weekday> = std::iter::successors(Some(week_start), |w| Some(w.succ()))
+            .take(7)
+            .collect();
         egui::Grid::new("header_grid")
-            .num_columns(7)
+            .num_columns(8)
             .min_col_width(120.0)
             .show(ui, |ui| {
                 for w in weekdays {
-                    let is_weekend = w == "Sun" || w == "Sat";
-                    egui::Frame::none()
+                    let is_weekend = matches!(w, Weekday::Sat | Weekday::Sun);
+                    egui::Frame::NONE
                         .fill(egui::Color32::from_rgb(245, 245, 245))
-                        .rounding(egui::Rounding::same(6))
+                        .corner_radius(egui::CornerRadius::same(6))
                         .show(ui, |ui| {
                             ui.centered_and_justified(|ui| {
                                 ui.label(
-                                    egui::RichText::new(w)
+                                    egui::RichText::new(w.to_string())
                                         .strong()
                                         .color(if is_weekend {
                                             egui::Color32::from_rgb(200, 60, 60)
@@ -213,23 +738,28 @@ impl App {
                             });
                         });
                 }
+                ui.label(egui::RichText::new(format!("Week · {}", self.job().name)).strong())
+                    .on_hover_text("Weekly totals of the selected job, in its own pay weeks");
                 ui.end_row();
             });
     
-        let start_wd = self.month_first.weekday().num_days_from_sunday() as usize;
+        let overlaps = overlap_dates(&self.entries);
+        let grid_start = week_rules.week_of(self.month_first);
+        let start_wd = (self.month_first - grid_start).num_days() as usize;
         let days_in_month = last_day(self.month_first.year(), self.month_first.month());
+        let month_last = NaiveDate::from_ymd_opt(y, m, days_in_month).unwrap();
         let mut day: u32 = 1;
         let mut started = false;
         let cell_size = egui::vec2(120.0, 96.0);
-        let cell_rounding = egui::Rounding::same(8);
+        let cell_rounding = egui::CornerRadius::same(8);
     
         // 📅 달력 테이블
         egui::Grid::new("calendar_grid")
-            .num_columns(7)
+            .num_columns(8)
             .min_col_width(cell_size.x)
             .min_row_height(cell_size.y)
             .show(ui, |ui| {
-                for _week in 0..6 {
+                for week in 0..6 {
                     for wd in 0..7usize {
                         if !started && wd == start_wd {
                             started = true;
@@ -239,7 +769,9 @@ impl App {
                             if let Some(date) = NaiveDate::from_ymd_opt(y, m, day) {
                                 let is_today = date == today;
                                 let is_selected = self.selected_date == Some(date);
-                                let is_weekend = wd == 0 || wd == 6; // ✅ 일요일(0) or 토요일(6)
+                                let is_weekend =
+                                    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+                                        || self.holidays.is_holiday(date);
     
                                 // 기본 배경색
                                 let mut bg = if is_weekend {
@@ -266,10 +798,10 @@ impl App {
                                         cell_size,
                                         egui::Layout::top_down(egui::Align::Min),
                                         |ui| {
-                                            egui::Frame::none()
+                                            egui::Frame::NONE
                                                 .fill(bg)
                                                 .stroke(border)
-                                                .rounding(cell_rounding)
+                                                .corner_radius(cell_rounding)
                                                 .inner_margin(egui::Margin::same(8))
                                                 .show(ui, |ui| {
                                                     ui.set_min_size(cell_size);
@@ -288,6 +820,19 @@ impl App {
                                                                         ),
                                                                 );
                                                             }
+                                                            if overlaps.contains(&date) {
+                                                                let orange =
+                                                                    egui::Color32::from_rgb(
+                                                                        200, 120, 0,
+                                                                    );
+                                                                ui.label(
+                                                                    egui::RichText::new("⚠")
+                                                                        .color(orange),
+                                                                )
+                                                                .on_hover_text(
+                                                                    "Overlapping entries",
+                                                                );
+                                                            }
                                                             ui.with_layout(
                                                                 egui::Layout::right_to_left(
                                                                     egui::Align::Center,
@@ -300,34 +845,37 @@ impl App {
                                                                 },
                                                             );
                                                         });
+                                                        if let Some(name) = self.holidays.name(date) {
+                                                            let red =
+                                                                egui::Color32::from_rgb(200, 60, 60);
+                                                            ui.label(
+                                                                egui::RichText::new(name)
+                                                                    .small()
+                                                                    .color(red),
+                                                            );
+                                                        }
                                                         ui.add_space(4.0);
 
                                                         if let Some(list) = self.entries.get(&date) {
-                                                            let mut day_total = 0.0;
-                                                            let mut day_hours = 0.0;
-                                                            for entry in list.iter() {
-                                                                if let Some(summary) =
-                                                                    calculate_pay_summary(
-                                                                        &entry.start,
-                                                                        &entry.end,
-                                                                        self.global_rate,
-                                                                    )
-                                                                {
-                                                                    day_total += summary.total_pay;
-                                                                    day_hours +=
-                                                                        summary.total_hours();
-                                                                }
-                                                            }
+                                                            let day = || {
+                                                                priced_on(&self.entries, ledger, date)
+                                                            };
+                                                            let day_hours: f64 = day()
+                                                                .map(|(_, _, s)| s.total_hours())
+                                                                .sum();
+                                                            let day_total =
+                                                                total_pay(day(), &self.jobs);
                                                             ui.small(format!(
-                                                                "{:.2}h / ${:.2}",
-                                                                day_hours, day_total
+                                                                "{:.2}h / {}",
+                                                                day_hours,
+                                                                self.currency.format(day_total)
                                                             ));
                                                             ui.add_space(4.0);
                                                             for entry in list.iter().take(3) {
-                                                                ui.small(format!(
-                                                                    "{}-{}",
-                                                                    entry.start, entry.end
-                                                                ));
+                                                                ui.label(
+                                                                    entry_text(&self.jobs, entry)
+                                                                        .small(),
+                                                                );
                                                             }
                                                             if list.len() > 3 {
                                                                 ui.small(format!(
@@ -351,9 +899,9 @@ impl App {
                             }
                             day += 1;
                         } else {
-                            egui::Frame::none()
+                            egui::Frame::NONE
                                 .stroke(egui::Stroke::new(0.5, egui::Color32::LIGHT_GRAY))
-                                .rounding(cell_rounding)
+                                .corner_radius(cell_rounding)
                                 .inner_margin(egui::Margin::same(8))
                                 .show(ui, |ui| {
                                     ui.add_sized(
@@ -363,180 +911,1747 @@ impl App {
                                 });
                         }
                     }
+
+                    // 주간 소계 (선택한 직장만, 그 직장의 급여 주 기준)
+                    let row_start = grid_start + Duration::days(7 * week);
+                    if row_start <= month_last {
+                        let allowance = allowances.get(&(self.job().id, row_start)).copied();
+                        self.week_subtotal_ui(ui, ledger, row_start, allowance, cell_size.y);
+                    }
                     ui.end_row();
                 }
             });
     }
-    
-    fn compute_totals(&self) -> (f64, f64) {
+
+    /// Combined totals, and the same totals for each job, rounded per each
+    /// job's rounding policy.
+    fn compute_totals(
+        &self,
+        ledger: &PayLedger,
+        allowances: &WeeklyAllowances,
+    ) -> (Totals, HashMap<JobId, Totals>) {
         let y = self.month_first.year();
         let m = self.month_first.month();
-        let mut month_total = 0.0;
-        let mut all_total = 0.0;
-
-        for (date, list) in &self.entries {
-            let mut day_sum = 0.0;
-            for e in list {
-                if let Some(summary) =
-                    calculate_pay_summary(&e.start, &e.end, self.global_rate)
-                {
-                    day_sum += summary.total_pay;
+        let in_month = |d: NaiveDate| d.year() == y && d.month() == m;
+        let items: Vec<_> = priced(&self.entries, ledger).collect();
+
+        let totals_of = |job: Option<JobId>| {
+            let mine = || {
+                items
+                    .iter()
+                    .copied()
+                    .filter(move |(_, e, _)| job.is_none_or(|id| e.job == id))
+            };
+            let mine_allowances = || {
+                allowances
+                    .iter()
+                    .filter(move |((j, _), _)| job.is_none_or(|id| *j == id))
+            };
+            Totals {
+                month: total_pay(mine().filter(|(d, _, _)| in_month(*d)), &self.jobs),
+                overall: total_pay(mine(), &self.jobs),
+                // 주휴수당은 주의 마지막 날이 속한 달에 포함
+                month_allowance: mine_allowances()
+                    .filter(|((_, week), _)| in_month(*week + Duration::days(6)))
+                    .map(|(_, amount)| *amount)
+                    .sum(),
+                overall_allowance: mine_allowances().map(|(_, amount)| *amount).sum(),
+            }
+        };
+
+        let per_job = self
+            .jobs
+            .iter()
+            .map(|j| (j.id, totals_of(Some(j.id))))
+            .collect();
+        (totals_of(None), per_job)
+    }
+
+    /// The trailing column of a calendar row: the active job's hours and pay
+    /// for the whole pay week starting at `week_start`, including days
+    /// outside the shown month. Other jobs may have other pay weeks, so they
+    /// are left out.
+    fn week_subtotal_ui(
+        &self,
+        ui: &mut egui::Ui,
+        ledger: &PayLedger,
+        week_start: NaiveDate,
+        allowance: Option<Money>,
+        height: f32,
+    ) {
+        let week = || {
+            week_start
+                .iter_days()
+                .take(7)
+                .flat_map(|date| priced_on(&self.entries, ledger, date))
+                .filter(|(_, e, _)| e.job == self.job().id)
+        };
+        let hours: f64 = week().map(|(_, _, s)| s.total_hours()).sum();
+        let overtime: f64 = week().map(|(_, _, s)| s.overtime_hours).sum();
+        let pay = total_pay(week(), &self.jobs);
+
+        egui::Frame::NONE
+            .fill(egui::Color32::from_rgb(245, 245, 245))
+            .corner_radius(egui::CornerRadius::same(8))
+            .inner_margin(egui::Margin::same(8))
+            .show(ui, |ui| {
+                ui.set_min_height(height);
+                ui.vertical(|ui| {
+                    ui.small(format!("{:.2}h", hours));
+                    if overtime > 0.0 {
+                        ui.small(format!("OT {:.2}h", overtime));
+                    }
+                    ui.strong(self.currency.format(pay));
+                    if let Some(allowance) = allowance {
+                        ui.small(format!("🎁 +{}", self.currency.format(allowance)));
+                    }
+                });
+            });
+    }
+
+    /// An hourly rate (entered in major units) in the app currency.
+    fn rate_text(&self, rate: f64) -> String {
+        self.currency.format(Money::from_major(rate, self.currency))
+    }
+}
+
+/// "HH:MM-HH:MM" in the colour of the entry's job.
+fn entry_text(jobs: &[Job], entry: &WorkEntry) -> egui::RichText {
+    let text = egui::RichText::new(format!(
+        "{}-{}",
+        hhmm(entry.start.time()),
+        hhmm_on(entry.end, entry.start.date())
+    ));
+    match find_job(jobs, entry.job) {
+        Some(job) => text.color(job_color(job)),
+        None => text,
+    }
+}
+
+fn job_color(job: &Job) -> egui::Color32 {
+    let [r, g, b] = job.color;
+    egui::Color32::from_rgb(r, g, b)
+}
+
+/// Combo box choosing one of `jobs`. Returns true when the choice changed.
+fn job_picker(ui: &mut egui::Ui, id: &str, jobs: &[Job], selected: &mut JobId) -> bool {
+    let before = *selected;
+    let name = find_job(jobs, *selected).map_or("", |j| j.name.as_str());
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(name)
+        .show_ui(ui, |ui| {
+            for job in jobs {
+                ui.selectable_value(
+                    selected,
+                    job.id,
+                    egui::RichText::new(&job.name).color(job_color(job)),
+                );
+            }
+        });
+    *selected != before
+}
+
+/* ---------- Entry popup ---------- */
+
+impl App {
+    /// The entries of `date` with their pay, each with edit, duplicate and
+    /// delete actions.
+    fn entry_list_ui(&mut self, ui: &mut egui::Ui, date: NaiveDate, ledger: &PayLedger) {
+        let Some(list) = self.entries.get(&date) else {
+            return;
+        };
+        let mut remove_idx: Option<usize> = None;
+        let mut start_edit: Option<(usize, bool)> = None;
+        let mut save_edit = false;
+        let mut cancel_edit = false;
+        let summaries = ledger.get(&date);
+        for (i, e) in list.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}.", i + 1));
+                ui.monospace(format!("{} - {}", hhmm(e.start.time()), hhmm_on(e.end, date)));
+                match find_job(&self.jobs, e.job) {
+                    Some(job) => ui.label(egui::RichText::new(&job.name).color(job_color(job))),
+                    None => ui.label("(deleted job)"),
+                };
+                for b in &e.breaks {
+                    ui.small(format!(
+                        "☕ {}-{}{}",
+                        hhmm(b.start),
+                        hhmm(b.end),
+                        if b.paid { " (paid)" } else { "" }
+                    ));
+                }
+                if let Some(summary) = summaries.and_then(|s| s.get(i)).and_then(Option::as_ref) {
+                    let summary = &summary.whole();
+                    if summary.start != e.start || summary.end != e.end {
+                        ui.monospace(format!(
+                            "→ {} - {}",
+                            hhmm_on(summary.start, date),
+                            hhmm_on(summary.end, date)
+                        ));
+                    }
+                    let c = self.currency;
+                    ui.small(format!(
+                        "{:.2}h reg + {:.2}h OT @ {}{} → {}",
+                        summary.regular_hours,
+                        summary.overtime_hours,
+                        c.format(summary.base_rate),
+                        if e.rate.is_some() { "*" } else { "" },
+                        c.format(summary.total_pay)
+                    ));
+                    if summary.night_pay > Money::ZERO {
+                        ui.small(format!(
+                            "🌙 {:.2}h night +{}",
+                            summary.night_hours,
+                            c.format(summary.night_pay)
+                        ));
+                    }
+                    if summary.weekend_pay > Money::ZERO {
+                        ui.small(format!(
+                            "🏖 {:.2}h weekend +{}",
+                            summary.weekend_hours,
+                            c.format(summary.weekend_pay)
+                        ));
+                    }
+                    if summary.holiday_pay > Money::ZERO {
+                        ui.small(format!(
+                            "🎌 {:.2}h holiday +{}",
+                            summary.holiday_hours,
+                            c.format(summary.holiday_pay)
+                        ));
+                    }
+                } else {
+                    ui.small("Not paid: no worked time or unknown job");
+                }
+                if ui.button("✏").on_hover_text("Edit").clicked() {
+                    start_edit = Some((i, false));
+                }
+                if ui.button("⧉").on_hover_text("Duplicate").clicked() {
+                    start_edit = Some((i, true));
+                }
+                if ui.button("🗑").clicked() {
+                    remove_idx = Some(i);
+                }
+            });
+
+            // 편집 중인 행은 바로 아래에 입력 폼
+            let Some(edit) = self
+                .editing
+                .as_mut()
+                .filter(|edit| edit.from == date && edit.index == i)
+            else {
+                continue;
+            };
+            egui::Frame::group(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(if edit.duplicate { "Copy to" } else { "Date" });
+                    ui.add(
+                        egui_extras::DatePickerButton::new(&mut edit.date).id_salt("edit_date"),
+                    );
+                    ui.label("Job");
+                    job_picker(ui, "edit_job", &self.jobs, &mut edit.job);
+                });
+                let rate_hint = match find_job(&self.jobs, edit.job) {
+                    Some(job) => format!(
+                        "(rate on this date: {})",
+                        self.currency.format(Money::from_major(
+                            job.rates.rate_on(edit.date),
+                            self.currency
+                        ))
+                    ),
+                    None => String::new(),
+                };
+                entry_form_ui(ui, &mut edit.form, self.currency, &rate_hint);
+                if let Some(err) = &edit.error {
+                    ui.colored_label(egui::Color32::from_rgb(190, 40, 40), err);
+                }
+                ui.horizontal(|ui| {
+                    let label = if edit.long_confirmed.is_some() {
+                        "Save anyway"
+                    } else {
+                        "💾 Save"
+                    };
+                    if ui.button(label).clicked() {
+                        save_edit = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel_edit = true;
+                    }
+                });
+            });
+        }
+
+        if let Some((index, duplicate)) = start_edit {
+            let entry = &self.entries[&date][index];
+            self.editing = Some(EntryEdit {
+                from: date,
+                index,
+                duplicate,
+                job: entry.job,
+                // 복제는 기본으로 다음 날에
+                date: if duplicate { date + Duration::days(1) } else { date },
+                form: EntryForm::from_entry(entry),
+                long_confirmed: None,
+                error: None,
+            });
+        }
+        if save_edit {
+            self.save_edit();
+        }
+        if cancel_edit {
+            self.editing = None;
+        }
+        if let Some(index) = remove_idx {
+            let entry = self.entries[&date][index].clone();
+            self.execute(Command::RemoveEntry { date, index, entry });
+            self.editing = None;
+        }
+    }
+
+    /// Validates the entry being edited like a new one and stores it,
+    /// moving it when its date changed.
+    fn save_edit(&mut self) {
+        let Some(mut edit) = self.editing.take() else {
+            return;
+        };
+        let skip = (!edit.duplicate).then_some((edit.from, edit.index));
+        let result = self
+            .entry_from_form(&edit.form, edit.date, edit.job, skip)
+            .map_err(|e| e.to_string())
+            .and_then(|entry| {
+                let minutes = (entry.end - entry.start).num_minutes();
+                let long = minutes as f64 > self.max_shift_hours * 60.0;
+                if long && edit.long_confirmed != Some(minutes) {
+                    edit.long_confirmed = Some(minutes);
+                    return Err(format!(
+                        "⚠ This shift is {:.1}h, longer than {:.0}h. Save anyway?",
+                        minutes as f64 / 60.0,
+                        self.max_shift_hours
+                    ));
+                }
+                if !self.has_paid_time(edit.date, &entry) {
+                    return Err("No paid time left after breaks.".into());
+                }
+                Ok(entry)
+            });
+        match result {
+            Ok(entry) => {
+                let mut commands = Vec::new();
+                if !edit.duplicate {
+                    commands.push(Command::RemoveEntry {
+                        date: edit.from,
+                        index: edit.index,
+                        entry: self.entries[&edit.from][edit.index].clone(),
+                    });
                 }
+                // 같은 날짜면 원래 자리에, 아니면 맨 뒤에
+                let index = if !edit.duplicate && edit.date == edit.from {
+                    edit.index
+                } else {
+                    self.entries.get(&edit.date).map_or(0, Vec::len)
+                };
+                commands.push(Command::InsertEntry {
+                    date: edit.date,
+                    index,
+                    entry,
+                });
+                self.execute(Command::Batch(commands));
             }
-            all_total += day_sum;
-            if date.year() == y && date.month() == m {
-                month_total += day_sum;
+            Err(err) => {
+                edit.error = Some(err);
+                self.editing = Some(edit);
             }
         }
-        (month_total, all_total)
     }
 }
 
-/* ---------- CSV I/O ---------- */
+/// Start/end, break and rate fields shared by the new-entry and edit forms.
+/// `rate_hint` is shown while the entry follows the job's rate.
+fn entry_form_ui(ui: &mut egui::Ui, form: &mut EntryForm, currency: Currency, rate_hint: &str) {
+    ui.horizontal(|ui| {
+        ui.label("Start");
+        ui.add(egui::TextEdit::singleline(&mut form.start).desired_width(80.0));
+        ui.label("End");
+        ui.add(egui::TextEdit::singleline(&mut form.end).desired_width(80.0));
+        ui.checkbox(&mut form.end_next_day, "Ends next day");
+    });
+    ui.horizontal(|ui| {
+        ui.label("☕ Breaks");
+        if ui.small_button("➕ Add break").clicked() {
+            form.breaks.push(BreakInput::default());
+        }
+    });
+    let mut remove_break: Option<usize> = None;
+    for (i, b) in form.breaks.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut b.start).desired_width(60.0));
+            ui.label("-");
+            ui.add(egui::TextEdit::singleline(&mut b.end).desired_width(60.0));
+            ui.checkbox(&mut b.paid, "paid");
+            if ui.small_button("🗑").clicked() {
+                remove_break = Some(i);
+            }
+        });
+    }
+    if let Some(i) = remove_break {
+        form.breaks.remove(i);
+    }
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut form.rate_override, "Own hourly rate");
+        ui.add_enabled(
+            form.rate_override,
+            egui::DragValue::new(&mut form.rate)
+                .range(0.0..=1_000_000.0)
+                .suffix(format!(" {}", currency.symbol())),
+        );
+        if !form.rate_override {
+            ui.small(rate_hint);
+        }
+    });
+}
+
+/* ---------- Settings UI ---------- */
 
 impl App {
-    fn save_csv(&self) {
-        if let Ok(mut f) = File::create(&self.csv_path) {
-            let _ = writeln!(
-                f,
-                "date,start,end,base_rate,regular_hours,overtime_hours,total"
-            );
-            for (date, list) in &self.entries {
-                for e in list {
-                    if let Some(summary) =
-                        calculate_pay_summary(&e.start, &e.end, self.global_rate)
-                    {
-                        let _ = writeln!(
-                            f,
-                            "{},{},{},{:.4},{:.4},{:.4},{:.4}",
-                            date,
-                            e.start,
-                            e.end,
-                            self.global_rate,
-                            summary.regular_hours,
-                            summary.overtime_hours,
-                            summary.total_pay
+    /// Appearance, currency and saving. Currency and saving belong to the
+    /// data file; the theme to this installation.
+    fn settings_ui(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        egui::Window::new("🔧 Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("settings_grid")
+                    .num_columns(2)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Theme");
+                        ui.horizontal(|ui| {
+                            let before = self.theme;
+                            for (theme, label) in [
+                                (egui::ThemePreference::System, "System"),
+                                (egui::ThemePreference::Light, "Light"),
+                                (egui::ThemePreference::Dark, "Dark"),
+                            ] {
+                                ui.radio_value(&mut self.theme, theme, label);
+                            }
+                            if self.theme != before {
+                                ctx.set_theme(self.theme);
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Currency");
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_salt("currency")
+                                .selected_text(self.currency.code())
+                                .show_ui(ui, |ui| {
+                                    for c in Currency::ALL {
+                                        ui.selectable_value(&mut self.currency, c, c.code());
+                                    }
+                                });
+                            ui.small("(all jobs)");
+                        });
+                        ui.end_row();
+
+                        ui.label("Saving");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.autosave, "Autosave");
+                            ui.label("Keep");
+                            ui.add(egui::DragValue::new(&mut self.backup_count).range(1..=100));
+                            ui.label("backups");
+                        });
+                        ui.end_row();
+
+                        ui.label("Long shifts");
+                        ui.horizontal(|ui| {
+                            ui.label("Ask before saving shifts over");
+                            ui.add(
+                                egui::DragValue::new(&mut self.max_shift_hours)
+                                    .range(1.0..=24.0)
+                                    .speed(0.5)
+                                    .suffix(" h"),
+                            );
+                        });
+                        ui.end_row();
+
+                        ui.label("Hourly rates");
+                        if ui.button("⚙ Jobs & Pay Rules…").clicked() {
+                            self.show_rules = true;
+                        }
+                        ui.end_row();
+                    });
+                ui.separator();
+                ui.small(format!(
+                    "Profile \"{}\": {}",
+                    self.workspace.current,
+                    self.storage.path().display()
+                ));
+                ui.small("Window size and the month shown are restored on the next start.");
+            });
+        self.show_settings = open;
+    }
+
+    fn pay_rules_ui(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        egui::Window::new("⚙ Jobs & Pay Rules")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                self.jobs_ui(ui);
+                ui.separator();
+
+                let symbol = format!(" {}", self.currency.symbol());
+                let job = self.job_mut();
+                let rules_before = job.rules.clone();
+                let mut rules_reset = false;
+                let rules = &mut job.rules;
+                egui::Grid::new("pay_rules_grid")
+                    .num_columns(2)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Overtime mode");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(
+                                &mut rules.overtime_mode,
+                                OvertimeMode::ClockTime,
+                                "After clock time",
+                            );
+                            ui.radio_value(
+                                &mut rules.overtime_mode,
+                                OvertimeMode::DailyHours,
+                                "After hours per day",
+                            );
+                        });
+                        ui.end_row();
+
+                        match rules.overtime_mode {
+                            OvertimeMode::ClockTime => {
+                                ui.label("Overtime starts at");
+                                ui.horizontal(|ui| time_edit(ui, &mut rules.overtime_start));
+                            }
+                            OvertimeMode::DailyHours => {
+                                ui.label("Overtime after");
+                                ui.add(
+                                    egui::DragValue::new(&mut rules.daily_overtime_after_hours)
+                                        .range(0.0..=24.0)
+                                        .speed(0.25)
+                                        .suffix(" h/day"),
+                                );
+                            }
+                        }
+                        ui.end_row();
+
+                        ui.label("Overtime multiplier");
+                        ui.add(
+                            egui::DragValue::new(&mut rules.overtime_multiplier)
+                                .range(1.0..=5.0)
+                                .speed(0.05)
+                                .suffix("×"),
+                        );
+                        ui.end_row();
+
+                        ui.label("Weekly overtime");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut rules.weekly_overtime_enabled, "after");
+                            ui.add_enabled(
+                                rules.weekly_overtime_enabled,
+                                egui::DragValue::new(&mut rules.weekly_overtime_after_hours)
+                                    .range(0.0..=168.0)
+                                    .speed(0.5)
+                                    .suffix(" h/week"),
+                            );
+                        });
+                        ui.end_row();
+
+                        ui.label("Week starts on");
+                        egui::ComboBox::from_id_salt("week_start")
+                            .selected_text(rules.week_start.to_string())
+                            .show_ui(ui, |ui| {
+                                for wd in [
+                                    Weekday::Mon,
+                                    Weekday::Tue,
+                                    Weekday::Wed,
+                                    Weekday::Thu,
+                                    Weekday::Fri,
+                                    Weekday::Sat,
+                                    Weekday::Sun,
+                                ] {
+                                    ui.selectable_value(&mut rules.week_start, wd, wd.to_string());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Night premium");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut rules.night_premium_enabled, "+");
+                            ui.add_enabled_ui(rules.night_premium_enabled, |ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut rules.night_premium)
+                                        .range(0.0..=5.0)
+                                        .speed(0.05)
+                                        .suffix("×"),
+                                );
+                                ui.label("from");
+                                time_edit(ui, &mut rules.night_start);
+                                ui.label("to");
+                                time_edit(ui, &mut rules.night_end);
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Weekend multiplier");
+                        ui.add(
+                            egui::DragValue::new(&mut rules.weekend_multiplier)
+                                .range(1.0..=5.0)
+                                .speed(0.05)
+                                .suffix("×"),
+                        );
+                        ui.end_row();
+
+                        ui.label("Holiday multiplier");
+                        ui.add(
+                            egui::DragValue::new(&mut rules.holiday_multiplier)
+                                .range(1.0..=5.0)
+                                .speed(0.05)
+                                .suffix("×"),
+                        );
+                        ui.end_row();
+
+                        ui.label("Weekly allowance (주휴수당)");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut rules.weekly_allowance_enabled, "from");
+                            ui.add_enabled(
+                                rules.weekly_allowance_enabled,
+                                egui::DragValue::new(&mut rules.weekly_allowance_min_hours)
+                                    .range(0.0..=40.0)
+                                    .speed(0.5)
+                                    .suffix(" h/week"),
+                            );
+                        });
+                        ui.end_row();
+
+                        ui.label("Scheduled days");
+                        ui.add_enabled_ui(rules.weekly_allowance_enabled, |ui| {
+                            ui.horizontal(|ui| {
+                                for wd in [
+                                    Weekday::Mon,
+                                    Weekday::Tue,
+                                    Weekday::Wed,
+                                    Weekday::Thu,
+                                    Weekday::Fri,
+                                    Weekday::Sat,
+                                    Weekday::Sun,
+                                ] {
+                                    let mut on = rules.scheduled_days.contains(&wd);
+                                    if ui.checkbox(&mut on, wd.to_string()).changed() {
+                                        if on {
+                                            rules.scheduled_days.push(wd);
+                                        } else {
+                                            rules.scheduled_days.retain(|d| *d != wd);
+                                        }
+                                    }
+                                }
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Breaks if none logged");
+                        ui.vertical(|ui| {
+                            let mut remove: Option<usize> = None;
+                            for (i, rule) in rules.statutory_breaks.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.add(
+                                        egui::DragValue::new(&mut rule.minutes)
+                                            .range(0..=240)
+                                            .suffix(" min"),
+                                    );
+                                    ui.label("for shifts of");
+                                    let mut hours = rule.after_minutes as f64 / 60.0;
+                                    if ui
+                                        .add(
+                                            egui::DragValue::new(&mut hours)
+                                                .range(0.0..=24.0)
+                                                .speed(0.25)
+                                                .suffix(" h+"),
+                                        )
+                                        .changed()
+                                    {
+                                        rule.after_minutes = (hours * 60.0).round() as i32;
+                                    }
+                                    if ui.small_button("🗑").clicked() {
+                                        remove = Some(i);
+                                    }
+                                });
+                            }
+                            if let Some(i) = remove {
+                                rules.statutory_breaks.remove(i);
+                            }
+                            if ui.small_button("➕ Add rule").clicked() {
+                                rules.statutory_breaks.push(BreakRule {
+                                    after_minutes: 4 * 60,
+                                    minutes: 30,
+                                });
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Round punches");
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_salt("punch_rounding")
+                                .selected_text(rules.punch_rounding.label())
+                                .show_ui(ui, |ui| {
+                                    for r in PunchRounding::ALL {
+                                        ui.selectable_value(
+                                            &mut rules.punch_rounding,
+                                            r,
+                                            r.label(),
+                                        );
+                                    }
+                                });
+                            ui.add_enabled_ui(rules.punch_rounding.uses_step(), |ui| {
+                                egui::ComboBox::from_id_salt("punch_rounding_step")
+                                    .selected_text(format!("{} min", rules.punch_rounding_step))
+                                    .show_ui(ui, |ui| {
+                                        for step in [5, 6, 10, 15, 30] {
+                                            ui.selectable_value(
+                                                &mut rules.punch_rounding_step,
+                                                step,
+                                                format!("{} min", step),
+                                            );
+                                        }
+                                    });
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Round pay");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(
+                                &mut rules.rounding,
+                                RoundingPolicy::Entry,
+                                "per entry",
+                            );
+                            ui.radio_value(&mut rules.rounding, RoundingPolicy::Day, "per day");
+                            ui.radio_value(
+                                &mut rules.rounding,
+                                RoundingPolicy::Period,
+                                "per pay week",
+                            );
+                        });
+                        ui.end_row();
+
+                        ui.label("Past midnight");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(
+                                &mut rules.day_attribution,
+                                DayAttribution::StartDay,
+                                "counts for start day",
+                            );
+                            ui.radio_value(
+                                &mut rules.day_attribution,
+                                DayAttribution::SplitAtMidnight,
+                                "split at midnight",
+                            );
+                        });
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Reset to defaults").clicked() {
+                        *rules = PayRules::default();
+                        rules_reset = true;
+                    }
+                    ui.small("Saved together with the entries.");
+                });
+                if job.rules != rules_before {
+                    let command = Command::SetRules {
+                        job: job.id,
+                        before: rules_before,
+                        after: job.rules.clone(),
+                    };
+                    // 초기화는 따로 한 단계, 나머지는 드래그처럼 묶음
+                    if rules_reset {
+                        self.history.record(command);
+                    } else {
+                        self.history.merge(command);
+                    }
+                }
+
+                ui.separator();
+                let job = self.job_mut();
+                let rates_before = job.rates.clone();
+                let mut rate_removed = false;
+                ui.collapsing("💱 Hourly rate history", |ui| {
+                    let rates = &mut job.rates;
+                    egui::Grid::new("rate_history_grid")
+                        .num_columns(3)
+                        .spacing([12.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Before first change");
+                            ui.add(
+                                egui::DragValue::new(&mut rates.base_rate)
+                                    .range(0.0..=1_000_000.0)
+                                    .suffix(symbol.as_str()),
+                            );
+                            ui.end_row();
+
+                            let mut remove: Option<NaiveDate> = None;
+                            for (from, rate) in rates.changes.iter_mut() {
+                                ui.monospace(format!("from {}", from));
+                                ui.add(
+                                    egui::DragValue::new(rate)
+                                        .range(0.0..=1_000_000.0)
+                                        .suffix(symbol.as_str()),
+                                );
+                                if ui.small_button("🗑").clicked() {
+                                    remove = Some(*from);
+                                }
+                                ui.end_row();
+                            }
+                            if let Some(from) = remove {
+                                rates.changes.remove(&from);
+                                rate_removed = true;
+                            }
+                        });
+                    ui.small("Add a change with the Hourly / from / Apply controls at the top.");
+                });
+                if job.rates != rates_before {
+                    let command = Command::SetRates {
+                        job: job.id,
+                        before: rates_before,
+                        after: job.rates.clone(),
+                    };
+                    // 드래그 중 바뀐 값은 한 단계로 묶음
+                    if rate_removed {
+                        self.history.record(command);
+                    } else {
+                        self.history.merge(command);
+                    }
+                }
+
+                ui.separator();
+                let holidays_before = self.holidays.clone();
+                ui.collapsing("🎌 Holidays & days off", |ui| {
+                    ui.checkbox(
+                        &mut self.holidays.korean_public_holidays,
+                        "Korean public holidays (incl. lunar and substitute days)",
+                    );
+                    ui.add_space(4.0);
+
+                    let mut remove: Option<NaiveDate> = None;
+                    egui::ScrollArea::vertical()
+                        .max_height(160.0)
+                        .show(ui, |ui| {
+                            for (date, name) in &self.holidays.custom {
+                                ui.horizontal(|ui| {
+                                    ui.monospace(date.to_string());
+                                    ui.label(name);
+                                    if ui.small_button("🗑").clicked() {
+                                        remove = Some(*date);
+                                    }
+                                });
+                            }
+                        });
+                    if let Some(date) = remove {
+                        self.holidays.custom.remove(&date);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui_extras::DatePickerButton::new(&mut self.new_holiday_date)
+                                .id_salt("new_holiday_date"),
                         );
+                        ui.text_edit_singleline(&mut self.new_holiday_name);
+                        if ui.button("➕ Add").clicked() {
+                            let name = if self.new_holiday_name.trim().is_empty() {
+                                "Holiday".to_string()
+                            } else {
+                                self.new_holiday_name.trim().to_string()
+                            };
+                            self.holidays.custom.insert(self.new_holiday_date, name);
+                            self.new_holiday_name.clear();
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Import .ics / .csv:");
+                        ui.text_edit_singleline(&mut self.holiday_import_path);
+                        if ui.button("📥 Import").clicked() {
+                            let path = self.holiday_import_path.trim();
+                            self.holiday_import_msg = Some(match self.holidays.import_file(path) {
+                                Ok(n) => format!("Imported {} day(s).", n),
+                                Err(e) => format!("Import failed: {}", e),
+                            });
+                        }
+                    });
+                    if let Some(msg) = &self.holiday_import_msg {
+                        ui.small(msg);
                     }
+                });
+                if self.holidays != holidays_before {
+                    self.history.record(Command::SetHolidays {
+                        before: holidays_before,
+                        after: self.holidays.clone(),
+                    });
                 }
-            }
-            println!("✅ Saved to {}", self.csv_path);
+            });
+        self.show_rules = open;
+        // 드래그나 입력이 끝나면 다음 변경은 새 단계로
+        let editing = ctx.dragged_id().is_some() || ctx.memory(|m| m.focused().is_some());
+        if !open || !editing {
+            self.history.close();
         }
     }
 
-    fn load_csv(&mut self) {
-        if let Ok(f) = OpenOptions::new().read(true).open(&self.csv_path) {
-            let reader = BufReader::new(f);
-            for (i, line) in reader.lines().enumerate() {
-                if let Ok(l) = line {
-                    if i == 0 && l.to_lowercase().starts_with("date,start,end") {
-                        continue;
-                    }
-                    let parts: Vec<&str> = l.split(',').collect();
-                    if parts.len() < 3 {
-                        continue;
-                    }
-                    if let Ok(date) = NaiveDate::parse_from_str(parts[0], "%Y-%m-%d") {
-                        let start = parts[1].trim().to_string();
-                        let end = parts[2].trim().to_string();
-                        self.entries
-                            .entry(date)
-                            .or_default()
-                            .push(WorkEntry { start, end });
+    /// Job picker plus name, colour, add and delete for the active job.
+    fn jobs_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Job");
+            job_picker(ui, "settings_job", &self.jobs, &mut self.active_job);
+            if ui.button("➕ New job").clicked() {
+                let id = self.jobs.iter().map(|j| j.id + 1).max().unwrap_or(0);
+                self.execute(Command::InsertJob {
+                    index: self.jobs.len(),
+                    job: Job::new(id, &format!("Job {}", id + 1)),
+                });
+                self.active_job = id;
+            }
+            // 마지막 직장은 삭제 불가, 항목은 첫 번째 남은 직장으로 이동
+            if ui
+                .add_enabled(self.jobs.len() > 1, egui::Button::new("🗑 Delete job"))
+                .clicked()
+            {
+                let removed = self.active_job;
+                let index = self.jobs.iter().position(|j| j.id == removed).unwrap_or(0);
+                let job = self.jobs[index].clone();
+                self.active_job = self.jobs[usize::from(index == 0)].id;
+                // 항목마다 빼고 다시 넣어서 날짜가 저장 대상이 되게
+                let mut commands = vec![Command::RemoveJob { index, job }];
+                for (date, list) in &self.entries {
+                    for (index, e) in list.iter().enumerate().filter(|(_, e)| e.job == removed) {
+                        commands.push(Command::RemoveEntry {
+                            date: *date,
+                            index,
+                            entry: e.clone(),
+                        });
+                        commands.push(Command::InsertEntry {
+                            date: *date,
+                            index,
+                            entry: WorkEntry {
+                                job: self.active_job,
+                                ..e.clone()
+                            },
+                        });
                     }
                 }
+                self.execute(Command::Batch(commands));
             }
-            println!("📂 Loaded from {}", self.csv_path);
+        });
+        let job = self.job_mut();
+        let before = (job.name.clone(), job.color);
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut job.name);
+            ui.label("Colour");
+            ui.color_edit_button_srgb(&mut job.color);
+        });
+        if job.name != before.0 || job.color != before.1 {
+            let command = Command::SetJobLabel {
+                job: job.id,
+                before,
+                after: (job.name.clone(), job.color),
+            };
+            self.history.merge(command);
+        }
+        ui.small("Rules and rates below apply to this job only.");
+    }
+
+    /// The entry typed into `form`, on `date` for `job`. `skip` is the entry
+    /// being edited, which the new one may overlap.
+    fn entry_from_form(
+        &self,
+        form: &EntryForm,
+        date: NaiveDate,
+        job: JobId,
+        skip: Option<(NaiveDate, usize)>,
+    ) -> Result<WorkEntry, EntryError> {
+        let start = date.and_time(parse_time(&form.start).map_err(EntryError::Start)?);
+        let mut end = date.and_time(parse_time(&form.end).map_err(EntryError::End)?);
+        if form.end_next_day {
+            end += Duration::days(1);
+        }
+        if start == end {
+            return Err(EntryError::ZeroDuration);
+        }
+        // 자정을 넘기는 근무는 명시적으로 체크해야 함
+        if end < start {
+            return Err(EntryError::EndBeforeStart);
+        }
+        if end - start > Duration::hours(24) {
+            return Err(EntryError::LongerThanDay);
+        }
+        let breaks = form
+            .breaks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| b.parse(i + 1, start, end))
+            .collect::<Result<Vec<_>, _>>()?;
+        let entry = WorkEntry {
+            start,
+            end,
+            rate: form.rate_override.then_some(form.rate),
+            job,
+            breaks,
+        };
+        if let Some((day, other)) = find_overlap(&self.entries, &entry, skip) {
+            return Err(EntryError::Overlaps(format!(
+                "{}-{} on {}",
+                hhmm(other.start.time()),
+                hhmm_on(other.end, day),
+                day
+            )));
+        }
+        Ok(entry)
+    }
+
+    /// Whether `entry` on `date` leaves any paid time after its breaks.
+    fn has_paid_time(&self, date: NaiveDate, entry: &WorkEntry) -> bool {
+        let Some(job) = find_job(&self.jobs, entry.job) else {
+            return false;
+        };
+        let rate = entry.rate.unwrap_or_else(|| job.rates.rate_on(date));
+        calculate_pay_summary(
+            entry,
+            Money::from_major(rate, self.currency),
+            &job.rules,
+            &self.holidays,
+            0,
+        )
+        .is_some()
+    }
+
+    /// Adds an entry typed into the popup unless its breaks leave no paid time.
+    fn add_entry(&mut self, date: NaiveDate, entry: WorkEntry) {
+        if self.has_paid_time(date, &entry) {
+            let index = self.entries.get(&date).map_or(0, Vec::len);
+            self.execute(Command::InsertEntry { date, index, entry });
+            let rate = self.new_entry.rate;
+            self.new_entry = EntryForm {
+                rate,
+                ..EntryForm::default()
+            };
+            self.popup_error = None;
+        } else {
+            self.popup_error = Some("No paid time left after breaks.".into());
+        }
+    }
+
+    fn execute(&mut self, command: Command) {
+        self.changed.extend(command.dates());
+        // 항목 전체가 바뀌면 편집 중인 항목의 위치도 무의미
+        if command.replaces_entries() {
+            self.editing = None;
+        }
+        self.history
+            .execute(command, &mut self.entries, &mut self.jobs, &mut self.holidays);
+    }
+
+    fn undo(&mut self) {
+        if let Some(command) = self
+            .history
+            .undo(&mut self.entries, &mut self.jobs, &mut self.holidays)
+        {
+            self.changed.extend(command.dates());
+            self.editing = None;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(command) = self
+            .history
+            .redo(&mut self.entries, &mut self.jobs, &mut self.holidays)
+        {
+            self.changed.extend(command.dates());
+            self.editing = None;
         }
     }
+
+    /// The active job, falling back to the first one.
+    fn job(&self) -> &Job {
+        find_job(&self.jobs, self.active_job).unwrap_or(&self.jobs[0])
+    }
+
+    fn job_mut(&mut self) -> &mut Job {
+        let i = self
+            .jobs
+            .iter()
+            .position(|j| j.id == self.active_job)
+            .unwrap_or(0);
+        &mut self.jobs[i]
+    }
 }
 
-/* ---------- Utils ---------- */
+/// An existing entry other than `skip` sharing time with `entry`. The
+/// dates either side are searched too, so overnight shifts are caught.
+fn find_overlap<'a>(
+    entries: &'a HashMap<NaiveDate, Vec<WorkEntry>>,
+    entry: &WorkEntry,
+    skip: Option<(NaiveDate, usize)>,
+) -> Option<(NaiveDate, &'a WorkEntry)> {
+    let date = entry.start.date();
+    (-1..=1)
+        .map(|d| date + Duration::days(d))
+        .flat_map(|d| {
+            let list = entries.get(&d).into_iter().flatten();
+            list.enumerate().map(move |(i, e)| (d, i, e))
+        })
+        .find(|(d, i, e)| skip != Some((*d, *i)) && e.overlaps(entry))
+        .map(|(d, _, e)| (d, e))
+}
+
+/// Dates with an entry that overlaps another one, e.g. after loading a
+/// file with duplicate rows.
+fn overlap_dates(entries: &HashMap<NaiveDate, Vec<WorkEntry>>) -> HashSet<NaiveDate> {
+    let mut all: Vec<(NaiveDate, &WorkEntry)> = entries
+        .iter()
+        .flat_map(|(d, list)| list.iter().map(move |e| (*d, e)))
+        .collect();
+    all.sort_by_key(|(_, e)| e.start);
 
-struct PaySummary {
-    regular_hours: f64,
-    overtime_hours: f64,
-    total_pay: f64,
+    // 시작 순으로 훑으며 지금까지 가장 늦게 끝나는 항목과 비교
+    let mut dates = HashSet::new();
+    let mut latest: Option<(NaiveDate, &WorkEntry)> = None;
+    for (date, e) in all {
+        if let Some((latest_date, latest_entry)) = latest {
+            if e.start < latest_entry.end {
+                dates.insert(latest_date);
+                dates.insert(date);
+            }
+            if e.end <= latest_entry.end {
+                continue;
+            }
+        }
+        latest = Some((date, e));
+    }
+    dates
 }
 
-impl PaySummary {
-    fn total_hours(&self) -> f64 {
-        self.regular_hours + self.overtime_hours
+/// Hour and minute drag values for a clock time.
+fn time_edit(ui: &mut egui::Ui, time: &mut NaiveTime) {
+    let mut h = time.hour();
+    let mut m = time.minute();
+    ui.add(egui::DragValue::new(&mut h).range(0..=23));
+    ui.label(":");
+    ui.add(egui::DragValue::new(&mut m).range(0..=59));
+    if let Some(t) = NaiveTime::from_hms_opt(h, m, 0) {
+        *time = t;
     }
 }
 
-fn calculate_pay_summary(start: &str, end: &str, base_rate: f64) -> Option<PaySummary> {
-    const MINUTES_PER_DAY: i32 = 24 * 60;
-    const OVERTIME_START_MIN: i32 = 15 * 60 + 30; // 15:30
-    const LUNCH_BREAK_MIN: i32 = 30;
+/* ---------- Data I/O ---------- */
+
+impl App {
+    /// Saves entries and settings, keeping a backup of the previous file.
+    /// A failure is shown as an error toast and leaves the data unsaved; a
+    /// failed backup is shown too, but the data is still saved.
+    fn save_data(&mut self) -> bool {
+        self.save(true)
+    }
+
+    fn save(&mut self, backup: bool) -> bool {
+        if self.read_only {
+            self.error_toast = Some(
+                "Not saved: no data file could be opened. Use 📁 File → Save as… to keep \
+                 these changes."
+                    .into(),
+            );
+            return false;
+        }
+        let path = self.storage.path().to_path_buf();
+        let mut backup_error = None;
+        if backup {
+            match backup::take_backup(&path, self.backup_count) {
+                Ok(()) => self.last_backup = Some(Instant::now()),
+                Err(e) => {
+                    backup_error = Some(format!("Backup of {} failed: {}", path.display(), e))
+                }
+            }
+        }
+        let settings = self.settings();
+        let data = Snapshot {
+            entries: &self.entries,
+            settings: &settings,
+        };
+        let saved = self.storage.save(&data, &self.changed).and_then(|_| {
+            if self.storage.keeps_settings() {
+                Ok(())
+            } else {
+                self.save_settings()
+            }
+        });
+        match saved {
+            Ok(()) => {
+                self.changed.clear();
+                self.saved_settings = self.settings();
+                // 저장은 됐어도 백업 실패는 알림
+                self.error_toast = backup_error;
+                true
+            }
+            Err(e) => {
+                self.error_toast = Some(format!("Save failed: {}", e));
+                false
+            }
+        }
+    }
+
+    /// Copies everything into a new file of `format` next to the current
+    /// one and keeps working in it. The old file is left as it was.
+    fn convert_to(&mut self, format: Format) -> Result<(), String> {
+        self.save_as(&self.storage.path().with_extension(format.extension()))
+    }
+
+    /// Writes everything to a new file at `target`, in the format its
+    /// extension names, and keeps working in it.
+    fn save_as(&mut self, target: &Path) -> Result<(), String> {
+        self.storage = self.write_copy(target)?;
+        self.read_only = false;
+        self.changed.clear();
+        self.saved_settings = self.settings();
+        self.workspace.remember(target);
+        self.save_workspace();
+        Ok(())
+    }
+
+    /// Writes everything to a new file at `target` without switching to it.
+    fn write_copy(&self, target: &Path) -> Result<Box<dyn Storage>, String> {
+        if target.exists() {
+            return Err(format!("{} already exists.", target.display()));
+        }
+        let settings = self.settings();
+        let data = Snapshot {
+            entries: &self.entries,
+            settings: &settings,
+        };
+        let all: HashSet<NaiveDate> = self.entries.keys().copied().collect();
+        let store = storage::open(target).and_then(|mut store| {
+            store.save(&data, &all)?;
+            if !store.keeps_settings() {
+                self.write_settings(&storage::settings_path(target))?;
+            }
+            Ok(store)
+        });
+        store.map_err(|e| {
+            let _ = std::fs::remove_file(target);
+            format!("Could not save: {}", e)
+        })
+    }
+
+    /// Switches to the data file at `path` and puts it first on the recent
+    /// list.
+    fn open_file(&mut self, path: &Path) -> Result<(), String> {
+        let path = self.load_file(path)?;
+        self.workspace.remember(&path);
+        self.save_workspace();
+        Ok(())
+    }
 
-    let s = parse_hhmm(start)?;
-    let e = parse_hhmm(end)?;
+    /// Switches to the data file at `path`, saving the current one first,
+    /// and returns its absolute path. A file that does not exist yet starts
+    /// out empty. If the file cannot be read, the current one stays open so
+    /// nothing is saved over it.
+    fn load_file(&mut self, path: &Path) -> Result<PathBuf, String> {
+        if self.is_dirty() && !self.save_data() {
+            return Err("Could not save the current file.".into());
+        }
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut store = storage::open(&path).map_err(|e| format!("Could not open {}", e))?;
+        let (settings, loaded) = read_store(store.as_mut())?;
+        self.storage = store;
+        self.read_only = false;
+        self.apply_settings(settings);
+        self.entries = loaded.entries;
+        self.changed.clear();
+        // 읽지 못한 줄은 조용히 버리지 않고 보여 줌
+        self.load_report = Some(loaded.report);
+        self.history = History::default();
+        self.editing = None;
+        self.show_popup = false;
+        self.saved_settings = self.settings();
+        self.last_backup = None;
+        let today = Local::now().naive_local().date();
+        self.new_rate = self.job().rates.rate_on(today);
+        Ok(path)
+    }
 
-    let start_min = (s.num_seconds_from_midnight() / 60) as i32;
-    let mut end_min = (e.num_seconds_from_midnight() / 60) as i32;
-    if end_min <= start_min {
-        end_min += MINUTES_PER_DAY;
+    /// Creates an empty data file at `path` and switches to it.
+    fn new_file(&mut self, path: &Path) -> Result<(), String> {
+        if path.exists() {
+            return Err(format!("{} already exists.", path.display()));
+        }
+        self.open_file(path)?;
+        if self.save(false) {
+            Ok(())
+        } else {
+            Err("Could not create the file.".into())
+        }
     }
-    let total_duration = end_min - start_min;
-    if total_duration <= 0 {
-        return None;
+
+    /// Saves the current file and opens the last file of profile `name`.
+    fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        if self.is_dirty() && !self.save_data() {
+            return Err("Could not save the current file.".into());
+        }
+        let previous = std::mem::replace(&mut self.workspace.current, name.to_string());
+        let path = self.workspace.start_file();
+        self.open_file(&path).inspect_err(|_| {
+            self.workspace.current = previous;
+        })
     }
 
-    let mut regular_minutes = 0i32;
-    let mut overtime_minutes = 0i32;
-    let mut cursor = start_min;
+    fn save_workspace(&mut self) {
+        if let Err(e) = self.workspace.save() {
+            self.error_toast = Some(format!("Could not save the recent files: {}", e));
+        }
+    }
 
-    while cursor < end_min {
-        let day_start = (cursor / MINUTES_PER_DAY) * MINUTES_PER_DAY;
-        let day_overtime_start = day_start + OVERTIME_START_MIN;
-        if cursor < day_overtime_start {
-            let segment_end = end_min.min(day_overtime_start);
-            regular_minutes += segment_end - cursor;
-            cursor = segment_end;
+    /// The 📁 File menu: files, recent files, conversion, backups and
+    /// profiles.
+    fn file_menu_ui(&mut self, ui: &mut egui::Ui) {
+        let here = self.storage.path().to_path_buf();
+        let dialog = |action, text: PathBuf| FileDialog {
+            action,
+            text: text.to_string_lossy().into_owned(),
+            error: None,
+        };
+        let mut result = Ok(());
+        ui.menu_button("📁 File", |ui| {
+            if ui.button("📄 New…").clicked() {
+                let dir = here.parent().map_or(PathBuf::new(), Path::to_path_buf);
+                self.file_dialog = Some(dialog(FileAction::New, dir.join("untitled.json")));
+                ui.close();
+            }
+            if ui.button("📂 Open…").clicked() {
+                self.file_dialog = Some(dialog(FileAction::Open, here.clone()));
+                ui.close();
+            }
+            ui.menu_button("🕘 Open recent", |ui| {
+                let recent = self.workspace.profile().recent.clone();
+                if recent.len() < 2 {
+                    ui.weak("No other files yet");
+                }
+                for path in recent.iter().filter(|p| **p != here) {
+                    if ui.button(path.display().to_string()).clicked() {
+                        result = self.open_file(path);
+                        ui.close();
+                    }
+                }
+            });
+            if ui.button("💾 Save as…").clicked() {
+                self.file_dialog = Some(dialog(FileAction::SaveAs, here.clone()));
+                ui.close();
+            }
+            ui.menu_button("🔁 Convert to", |ui| {
+                for format in Format::ALL {
+                    let current = self.storage.format() == format;
+                    if ui.add_enabled(!current, egui::Button::new(format.label())).clicked() {
+                        result = self.convert_to(format);
+                        ui.close();
+                    }
+                }
+            });
+            if ui.button("🗂 Restore backup…").clicked() {
+                self.show_backups = true;
+                ui.close();
+            }
+            ui.separator();
+            ui.menu_button(format!("👤 Profile: {}", self.workspace.current), |ui| {
+                let names: Vec<String> =
+                    self.workspace.profiles.iter().map(|p| p.name.clone()).collect();
+                for name in names {
+                    if ui.radio(name == self.workspace.current, &name).clicked() {
+                        result = self.switch_profile(&name);
+                        ui.close();
+                    }
+                }
+                ui.separator();
+                if ui.button("➕ New profile…").clicked() {
+                    self.file_dialog = Some(dialog(FileAction::NewProfile, PathBuf::new()));
+                    ui.close();
+                }
+            });
+        })
+        .response
+        .on_hover_text(here.display().to_string());
+        if let Err(e) = result {
+            self.error_toast = Some(e);
+        }
+    }
+
+    /// Asks for the path or profile name a File menu item needs.
+    fn file_dialog_ui(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.file_dialog else {
+            return;
+        };
+        let (title, ok) = match dialog.action {
+            FileAction::Open => ("📂 Open", "Open"),
+            FileAction::New => ("📄 New file", "Create"),
+            FileAction::SaveAs => ("💾 Save as", "Save"),
+            FileAction::NewProfile => ("👤 New profile", "Create"),
+        };
+        let mut confirm = false;
+        let mut cancel = false;
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let naming = dialog.action == FileAction::NewProfile;
+                ui.label(if naming { "Name:" } else { "File:" });
+                let edit =
+                    ui.add(egui::TextEdit::singleline(&mut dialog.text).desired_width(380.0));
+                if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    confirm = true;
+                }
+                if matches!(dialog.action, FileAction::New | FileAction::SaveAs) {
+                    // 형식을 고르면 확장자를 바꿈
+                    let typed = Format::of(Path::new(&dialog.text));
+                    let mut format = typed;
+                    egui::ComboBox::from_id_salt("file_format")
+                        .selected_text(format.label())
+                        .show_ui(ui, |ui| {
+                            for f in Format::ALL {
+                                ui.selectable_value(&mut format, f, f.label());
+                            }
+                        });
+                    if format != typed {
+                        let path = Path::new(&dialog.text).with_extension(format.extension());
+                        dialog.text = path.to_string_lossy().into_owned();
+                    }
+                }
+                if naming {
+                    ui.small("Every profile has its own data file and settings.");
+                }
+                if let Some(err) = &dialog.error {
+                    ui.colored_label(egui::Color32::from_rgb(190, 40, 40), err);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button(ok).clicked() {
+                        confirm = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if cancel {
+            self.file_dialog = None;
+        }
+        if !confirm {
+            return;
+        }
+        let Some(dialog) = self.file_dialog.take() else {
+            return;
+        };
+        let text = dialog.text.trim();
+        let path = Path::new(text);
+        let result = if text.is_empty() && dialog.action != FileAction::NewProfile {
+            Err("Enter a file path.".into())
         } else {
-            let day_end = day_start + MINUTES_PER_DAY;
-            let segment_end = end_min.min(day_end);
-            overtime_minutes += segment_end - cursor;
-            cursor = segment_end;
+            match dialog.action {
+                FileAction::Open if !path.exists() => {
+                    Err(format!("{} does not exist.", path.display()))
+                }
+                FileAction::Open => self.open_file(path),
+                FileAction::New => self.new_file(path),
+                FileAction::SaveAs => self.save_as(path),
+                FileAction::NewProfile => self
+                    .workspace
+                    .add_profile(text)
+                    .and_then(|_| self.switch_profile(text.trim())),
+            }
+        };
+        if let Err(e) = result {
+            self.file_dialog = Some(FileDialog {
+                error: Some(e),
+                ..dialog
+            });
         }
     }
 
-    let mut remaining_lunch = LUNCH_BREAK_MIN.min(total_duration);
-    if regular_minutes >= remaining_lunch {
-        regular_minutes -= remaining_lunch;
-        remaining_lunch = 0;
-    } else {
-        remaining_lunch -= regular_minutes;
-        regular_minutes = 0;
+    /// Lists the lines the last load had to skip.
+    fn load_report_ui(&mut self, ctx: &egui::Context) {
+        let Some(report) = &self.load_report else {
+            return;
+        };
+        let mut dismiss = false;
+        egui::Window::new("⚠ Some lines were not loaded")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{}: {} entries loaded, {} line(s) skipped.",
+                    report.path,
+                    report.loaded,
+                    report.rejected.len()
+                ));
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("rejected_lines_grid")
+                            .num_columns(2)
+                            .spacing([12.0, 4.0])
+                            .show(ui, |ui| {
+                                for r in &report.rejected {
+                                    ui.monospace(format!("line {}", r.line));
+                                    ui.label(&r.reason);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                // 데이터베이스는 바뀐 날짜만 다시 쓰므로 건너뛴 행이 남음
+                if Format::of(Path::new(&report.path)) == Format::Csv {
+                    ui.small("Skipped lines are dropped from the file on the next save.");
+                }
+                if ui.button("OK").clicked() {
+                    dismiss = true;
+                }
+            });
+        if dismiss {
+            self.load_report = None;
+        }
+    }
+
+    /// Lists the backups of the data file; restoring one replaces the
+    /// entries and can be undone.
+    fn backups_ui(&mut self, ctx: &egui::Context) {
+        let backups = backup::list_backups(self.storage.path());
+        let mut restore: Option<PathBuf> = None;
+        let mut open = true;
+        egui::Window::new("🗂 Restore backup")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if backups.is_empty() {
+                    ui.label("No backups yet. One is taken whenever the data is saved.");
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("backups_grid")
+                            .num_columns(3)
+                            .spacing([12.0, 4.0])
+                            .show(ui, |ui| {
+                                for b in &backups {
+                                    ui.monospace(b.taken.format("%Y-%m-%d %H:%M:%S").to_string());
+                                    ui.label(format!("{:.1} KB", b.bytes as f64 / 1024.0));
+                                    if ui.button("Restore").clicked() {
+                                        restore = Some(b.path.clone());
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                ui.small(format!(
+                    "Kept in the backups folder next to {} (last {}).",
+                    self.storage.path().display(),
+                    self.backup_count
+                ));
+            });
+        self.show_backups = open;
+
+        if let Some(path) = restore {
+            // 직장은 지금 것을 두고, 백업에만 있는 직장만 추가
+            let mut jobs = self.jobs.clone();
+            match storage::open(&path).and_then(|mut store| store.load(&mut jobs)) {
+                Ok(loaded) => {
+                    let jobs = loaded.settings.map_or(jobs, |s| s.jobs);
+                    // 되돌리면 추가한 직장도 함께 빠지도록 한 단계로
+                    let mut commands: Vec<Command> = jobs
+                        .into_iter()
+                        .filter(|job| find_job(&self.jobs, job.id).is_none())
+                        .enumerate()
+                        .map(|(i, job)| Command::InsertJob {
+                            index: self.jobs.len() + i,
+                            job,
+                        })
+                        .collect();
+                    commands.push(Command::SetEntries {
+                        before: self.entries.clone(),
+                        after: loaded.entries,
+                    });
+                    self.execute(Command::Batch(commands));
+                    self.load_report = Some(loaded.report);
+                    self.show_backups = false;
+                }
+                Err(e) => self.error_toast = Some(format!("Could not read {}", e)),
+            }
+        }
+    }
+
+    /// Asks whether to save before the window closes with unsaved changes.
+    fn close_prompt_ui(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Unsaved changes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("Save your changes before closing?");
+                ui.horizontal(|ui| {
+                    if ui.button("💾 Save and close").clicked() && self.save_data() {
+                        self.allow_close = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                    if ui.button("Don't save").clicked() {
+                        self.allow_close = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.show_close_prompt = false;
+                    }
+                });
+            });
     }
-    if remaining_lunch > 0 {
-        overtime_minutes = (overtime_minutes - remaining_lunch).max(0);
+
+    /// A failed save or load, shown in the corner until dismissed.
+    fn error_toast_ui(&mut self, ctx: &egui::Context) {
+        let Some(err) = &self.error_toast else {
+            return;
+        };
+        let mut dismiss = false;
+        egui::Area::new(egui::Id::new("error_toast"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-12.0, -12.0])
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .fill(egui::Color32::from_rgb(255, 235, 235))
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(190, 40, 40),
+                                format!("⚠ {}", err),
+                            );
+                            if ui.small_button("✖").clicked() {
+                                dismiss = true;
+                            }
+                        });
+                    });
+            });
+        if dismiss {
+            self.error_toast = None;
+        }
+    }
+}
+
+/* ---------- Settings I/O ---------- */
+
+impl App {
+    fn settings_path(&self) -> PathBuf {
+        storage::settings_path(self.storage.path())
+    }
+
+    fn settings(&self) -> Settings {
+        Settings {
+            jobs: self.jobs.clone(),
+            active_job: self.active_job,
+            currency: self.currency,
+            holidays: self.holidays.clone(),
+            max_shift_hours: self.max_shift_hours,
+            autosave: self.autosave,
+            backup_count: self.backup_count,
+            ..Default::default()
+        }
     }
 
-    let worked_minutes = regular_minutes + overtime_minutes;
-    if worked_minutes <= 0 {
-        return None;
+    /// Whether the settings differ from the saved ones, compared field by
+    /// field as this runs several times a frame. Switching the active job is
+    /// not a change worth saving.
+    fn settings_changed(&self) -> bool {
+        let saved = &self.saved_settings;
+        self.jobs != saved.jobs
+            || self.currency != saved.currency
+            || self.holidays != saved.holidays
+            || self.max_shift_hours != saved.max_shift_hours
+            || self.autosave != saved.autosave
+            || self.backup_count != saved.backup_count
     }
 
-    let regular_hours = regular_minutes as f64 / 60.0;
-    let overtime_hours = overtime_minutes as f64 / 60.0;
-    let total_pay = regular_hours * base_rate + overtime_hours * base_rate * 1.5;
+    fn is_dirty(&self) -> bool {
+        !self.changed.is_empty() || self.settings_changed()
+    }
 
-    Some(PaySummary {
-        regular_hours,
-        overtime_hours,
-        total_pay,
-    })
+    fn save_settings(&self) -> Result<(), String> {
+        self.write_settings(&self.settings_path())
+    }
+
+    fn write_settings(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.settings()).map_err(|e| e.to_string())?;
+        backup::write_atomic(path, |f| f.write_all(json.as_bytes()))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    fn apply_settings(&mut self, mut settings: Settings) {
+        settings.upgrade();
+        self.jobs = settings.jobs;
+        self.active_job = settings.active_job;
+        self.currency = settings.currency;
+        self.holidays = settings.holidays;
+        self.max_shift_hours = settings.max_shift_hours;
+        self.autosave = settings.autosave;
+        self.backup_count = settings.backup_count;
+    }
+}
+
+impl Settings {
+    /// Turns single-job settings written before jobs existed into one job.
+    fn upgrade(&mut self) {
+        if self.jobs.is_empty() {
+            self.jobs.push(Job {
+                rates: self.rates.take().unwrap_or_default(),
+                rules: self.pay_rules.take().unwrap_or_default(),
+                ..Job::default()
+            });
+        }
+    }
+}
+
+/// The settings file at `path`; defaults when there is none yet.
+fn read_settings(path: &Path) -> Result<Settings, String> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(format!("Could not read {}: {}", path.display(), e)),
+    }
 }
 
-fn parse_hhmm(s: &str) -> Option<NaiveTime> {
-    let (h, m) = s.split_once(':')?;
-    let hh: u32 = h.parse().ok()?;
-    let mm: u32 = m.parse().ok()?;
-    NaiveTime::from_hms_opt(hh, mm, 0)
+/// The settings and entries of `store`, without touching the app, so that
+/// a file that cannot be read leaves everything as it was.
+fn read_store(store: &mut dyn Storage) -> Result<(Settings, storage::Loaded), String> {
+    let mut settings = if store.keeps_settings() {
+        Settings::default()
+    } else {
+        // 예전 이름의 설정 파일은 새 이름으로 저장될 때까지 그대로 읽음
+        let path = storage::settings_path(store.path());
+        match storage::legacy_settings_path(store.path()) {
+            Some(legacy) if !path.exists() && legacy.exists() => read_settings(&legacy)?,
+            _ => read_settings(&path)?,
+        }
+    };
+    settings.upgrade();
+    let mut loaded = store
+        .load(&mut settings.jobs)
+        .map_err(|e| format!("Could not read {}", e))?;
+    if let Some(own) = loaded.settings.take() {
+        settings = own;
+    }
+    Ok((settings, loaded))
 }
 
+/* ---------- Utils ---------- */
+
 fn last_day(year: i32, month: u32) -> u32 {
     let next = if month == 12 {
         NaiveDate::from_ymd_opt(year + 1, 1, 1).unwrap()
@@ -555,13 +2670,68 @@ fn month_name(m: u32) -> &'static str {
 }
 
 fn main() -> eframe::Result<()> {
+    // 명령이 주어지면 창 없이 실행
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
     let opts = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([760.0, 660.0]),
+        viewport: egui::ViewportBuilder::default().with_inner_size([1000.0, 720.0]),
         ..Default::default()
     };
     eframe::run_native(
-        "Money Calendar",
+        workspace::APP_ID,
         opts,
-        Box::new(|_| Ok(Box::new(App::default()))),
+        Box::new(|cc| Ok(Box::new(App::new(cc)))),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(start: NaiveDateTime, hours: i64) -> WorkEntry {
+        WorkEntry {
+            start,
+            end: start + Duration::hours(hours),
+            rate: None,
+            job: 0,
+            breaks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn catches_an_overnight_shift_running_into_the_next_day() {
+        let monday = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        // 월 22:00–화 06:00, 화 05:00부터 근무
+        let night = entry(monday.and_hms_opt(22, 0, 0).unwrap(), 8);
+        let early = entry(tuesday.and_hms_opt(5, 0, 0).unwrap(), 4);
+        let entries = HashMap::from([(monday, vec![night.clone()])]);
+
+        let found = find_overlap(&entries, &early, None).map(|(d, e)| (d, e.start));
+        assert_eq!(found, Some((monday, night.start)));
+        // 편집 중인 항목 자신과는 겹치지 않음
+        assert!(find_overlap(&entries, &night, Some((monday, 0))).is_none());
+
+        let entries = HashMap::from([(monday, vec![night]), (tuesday, vec![early])]);
+        assert_eq!(overlap_dates(&entries), HashSet::from([monday, tuesday]));
+    }
+
+    #[test]
+    fn allows_shifts_that_only_touch() {
+        let monday = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        let night = entry(monday.and_hms_opt(22, 0, 0).unwrap(), 8);
+        let morning = entry(tuesday.and_hms_opt(6, 0, 0).unwrap(), 4);
+        let entries = HashMap::from([(monday, vec![night.clone()])]);
+        assert!(find_overlap(&entries, &morning, None).is_none());
+
+        let evening = entry(monday.and_hms_opt(18, 0, 0).unwrap(), 4);
+        let entries = HashMap::from([
+            (monday, vec![evening, night]),
+            (tuesday, vec![morning]),
+        ]);
+        assert!(overlap_dates(&entries).is_empty());
+    }
+}
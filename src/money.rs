@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
+
+/* ---------- Currency ---------- */
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Currency {
+    #[default]
+    Usd,
+    Eur,
+    Krw,
+    Jpy,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 4] = [Currency::Usd, Currency::Eur, Currency::Krw, Currency::Jpy];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Krw => "KRW",
+            Currency::Jpy => "JPY",
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Currency::Usd => "$",
+            Currency::Eur => "€",
+            Currency::Krw => "₩",
+            Currency::Jpy => "¥",
+        }
+    }
+
+    /// Digits after the decimal point; won and yen have no minor unit.
+    fn minor_digits(self) -> u32 {
+        match self {
+            Currency::Usd | Currency::Eur => 2,
+            Currency::Krw | Currency::Jpy => 0,
+        }
+    }
+
+    fn minor_per_major(self) -> i64 {
+        10i64.pow(self.minor_digits())
+    }
+
+    /// "$12.34", "-₩9860".
+    pub fn format(self, money: Money) -> String {
+        let sign = if money.0 < 0 { "-" } else { "" };
+        format!(
+            "{}{}{}",
+            sign,
+            self.symbol(),
+            self.plain(Money(money.0.abs()))
+        )
+    }
+
+    /// The amount without symbol, as written to CSV: "12.34", "9860".
+    pub fn plain(self, money: Money) -> String {
+        let per = self.minor_per_major();
+        let sign = if money.0 < 0 { "-" } else { "" };
+        let abs = money.0.abs();
+        if per == 1 {
+            format!("{}{}", sign, abs)
+        } else {
+            format!(
+                "{}{}.{:0width$}",
+                sign,
+                abs / per,
+                abs % per,
+                width = self.minor_digits() as usize
+            )
+        }
+    }
+}
+
+/* ---------- Money ---------- */
+
+/// An amount in integer minor units (cents, won) of the app currency.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    /// Converts a user-entered amount such as an hourly rate, rounding to
+    /// the nearest minor unit.
+    pub fn from_major(amount: f64, currency: Currency) -> Money {
+        Money((amount * currency.minor_per_major() as f64).round() as i64)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+/// Multipliers are taken to four decimal places.
+const BASIS_POINTS: i128 = 10_000;
+
+/// Unrounded pay: minutes × rate × multiplier, kept exactly as a count of
+/// 1/(60 × 10 000) minor units so that sums never drift.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PreciseMoney(i128);
+
+impl PreciseMoney {
+    pub const ZERO: PreciseMoney = PreciseMoney(0);
+
+    pub fn of(minutes: i64, hourly: Money, multiplier: f64) -> PreciseMoney {
+        let bp = (multiplier * BASIS_POINTS as f64).round() as i128;
+        PreciseMoney(minutes as i128 * hourly.0 as i128 * bp)
+    }
+
+    /// Rounds half away from zero to a whole minor unit.
+    pub fn round(self) -> Money {
+        let unit = 60 * BASIS_POINTS;
+        let half = unit / 2;
+        let minor = if self.0 >= 0 {
+            (self.0 + half) / unit
+        } else {
+            (self.0 - half) / unit
+        };
+        Money(minor as i64)
+    }
+}
+
+impl Add for PreciseMoney {
+    type Output = PreciseMoney;
+    fn add(self, rhs: PreciseMoney) -> PreciseMoney {
+        PreciseMoney(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for PreciseMoney {
+    fn add_assign(&mut self, rhs: PreciseMoney) {
+        self.0 += rhs.0;
+    }
+}
+
+/// When pay is rounded to whole minor units.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoundingPolicy {
+    /// Every entry is rounded; totals add the rounded amounts.
+    #[default]
+    Entry,
+    /// Entries of a date are added up unrounded, then each day is rounded.
+    Day,
+    /// Each pay week is rounded once.
+    Period,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `minutes` at one minor unit per hour.
+    fn per_hour(minutes: i64) -> PreciseMoney {
+        PreciseMoney::of(minutes, Money(1), 1.0)
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero() {
+        assert_eq!(per_hour(30).round(), Money(1));
+        assert_eq!(per_hour(29).round(), Money(0));
+        assert_eq!(per_hour(90).round(), Money(2));
+        assert_eq!(per_hour(-30).round(), Money(-1));
+        assert_eq!(per_hour(-29).round(), Money(0));
+    }
+
+    #[test]
+    fn rounding_once_differs_from_rounding_each_part() {
+        // 1 minute at $10.01/h is 16.68¢
+        let minute = PreciseMoney::of(1, Money::from_major(10.01, Currency::Usd), 1.0);
+        let each: Money = (0..3).map(|_| minute.round()).sum();
+        let once = (minute + minute + minute).round();
+        assert_eq!(each, Money(51));
+        assert_eq!(once, Money(50));
+    }
+
+    #[test]
+    fn formats_in_minor_units() {
+        assert_eq!(Currency::Usd.format(Money(-1205)), "-$12.05");
+        assert_eq!(Currency::Eur.plain(Money(7)), "0.07");
+        assert_eq!(Currency::Krw.format(Money::from_major(9860.0, Currency::Krw)), "₩9860");
+    }
+}
@@ -0,0 +1,1183 @@
+use crate::holidays::HolidayCalendar;
+use crate::money::{Currency, Money, PreciseMoney, RoundingPolicy};
+use crate::WorkEntry;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/* ---------- Pay rules ---------- */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OvertimeMode {
+    /// Minutes after `overtime_start` on the clock are overtime.
+    ClockTime,
+    /// Minutes beyond `daily_overtime_after_hours` worked on one date are overtime.
+    DailyHours,
+}
+
+/// A shift of at least `after_minutes` owes an unpaid break of `minutes`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreakRule {
+    pub after_minutes: i32,
+    pub minutes: i32,
+}
+
+/// How clock-in and clock-out times are rounded before pay is worked out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PunchRounding {
+    #[default]
+    Off,
+    /// To the nearest step; half-way rounds up.
+    Nearest,
+    Up,
+    Down,
+    /// Clock-in rounds up and clock-out rounds down.
+    EmployerFavourable,
+    /// US quarter-hour rule: 1–7 minutes round down, 8–14 round up.
+    SevenMinuteRule,
+}
+
+impl PunchRounding {
+    pub const ALL: [PunchRounding; 6] = [
+        PunchRounding::Off,
+        PunchRounding::Nearest,
+        PunchRounding::Up,
+        PunchRounding::Down,
+        PunchRounding::EmployerFavourable,
+        PunchRounding::SevenMinuteRule,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PunchRounding::Off => "Off",
+            PunchRounding::Nearest => "Nearest",
+            PunchRounding::Up => "Up",
+            PunchRounding::Down => "Down",
+            PunchRounding::EmployerFavourable => "Employer-favourable",
+            PunchRounding::SevenMinuteRule => "7-minute rule",
+        }
+    }
+
+    /// Whether the policy uses `punch_rounding_step`.
+    pub fn uses_step(self) -> bool {
+        !matches!(self, PunchRounding::Off | PunchRounding::SevenMinuteRule)
+    }
+}
+
+/// Which date the hours of a shift that runs past midnight are booked on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DayAttribution {
+    /// The whole shift counts towards the date it started on.
+    #[default]
+    StartDay,
+    /// Minutes after midnight count towards the next date.
+    SplitAtMidnight,
+}
+
+/// Contract-specific knobs used by `calculate_pay_summary`.
+///
+/// Premiums stack additively on top of the base rate: a Sunday overtime
+/// minute with a 1.5× overtime and a 1.5× weekend multiplier is paid at
+/// 1 + 0.5 + 0.5 = 2.0×, plus the night premium if it applies.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, remote = "Self")]
+pub struct PayRules {
+    pub overtime_mode: OvertimeMode,
+    /// Wall-clock time after which minutes are paid as overtime.
+    pub overtime_start: NaiveTime,
+    /// Hours per date paid at the regular rate in `OvertimeMode::DailyHours`.
+    pub daily_overtime_after_hours: f64,
+    pub overtime_multiplier: f64,
+    /// Minimum unpaid breaks deducted from shifts without logged breaks.
+    pub statutory_breaks: Vec<BreakRule>,
+    /// Reclassify regular hours beyond `weekly_overtime_after_hours` as overtime.
+    pub weekly_overtime_enabled: bool,
+    pub weekly_overtime_after_hours: f64,
+    /// First day of the pay week; also the first column of the calendar.
+    pub week_start: Weekday,
+    /// Extra pay for minutes inside the night window, on top of overtime.
+    pub night_premium_enabled: bool,
+    pub night_start: NaiveTime,
+    pub night_end: NaiveTime,
+    /// Added to the multiplier of night minutes, e.g. 0.5 → +0.5×.
+    pub night_premium: f64,
+    /// Multiplier for minutes worked on Saturdays and Sundays.
+    pub weekend_multiplier: f64,
+    /// Multiplier for minutes worked on holidays; replaces the weekend one
+    /// when it is higher.
+    pub holiday_multiplier: f64,
+    /// Korean weekly holiday allowance (주휴수당).
+    pub weekly_allowance_enabled: bool,
+    /// Minimum regular hours in a week to qualify for the allowance.
+    pub weekly_allowance_min_hours: f64,
+    /// Days that must each have an entry (or be a holiday) to qualify.
+    pub scheduled_days: Vec<Weekday>,
+    /// Where pay is rounded to whole cents (or won).
+    pub rounding: RoundingPolicy,
+    pub punch_rounding: PunchRounding,
+    /// Minutes to round punches to, e.g. 5, 10 or 15.
+    pub punch_rounding_step: u32,
+    pub day_attribution: DayAttribution,
+}
+
+impl Default for PayRules {
+    fn default() -> Self {
+        Self {
+            overtime_mode: OvertimeMode::ClockTime,
+            overtime_start: NaiveTime::from_hms_opt(15, 30, 0).unwrap(),
+            daily_overtime_after_hours: 8.0,
+            overtime_multiplier: 1.5,
+            statutory_breaks: vec![
+                BreakRule {
+                    after_minutes: 4 * 60,
+                    minutes: 30,
+                },
+                BreakRule {
+                    after_minutes: 8 * 60,
+                    minutes: 60,
+                },
+            ],
+            weekly_overtime_enabled: false,
+            weekly_overtime_after_hours: 40.0,
+            week_start: Weekday::Sun,
+            night_premium_enabled: false,
+            night_start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            night_end: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            night_premium: 0.5,
+            weekend_multiplier: 1.0,
+            holiday_multiplier: 1.0,
+            weekly_allowance_enabled: false,
+            weekly_allowance_min_hours: 15.0,
+            scheduled_days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            rounding: RoundingPolicy::Entry,
+            punch_rounding: PunchRounding::Off,
+            punch_rounding_step: 15,
+            day_attribution: DayAttribution::StartDay,
+        }
+    }
+}
+
+impl Serialize for PayRules {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PayRules::serialize(self, serializer)
+    }
+}
+
+/// The flat lunch break of rules saved before `statutory_breaks` existed.
+#[derive(Deserialize)]
+struct LegacyLunch {
+    lunch_minutes: Option<i32>,
+    #[serde(default)]
+    lunch_trigger_minutes: i32,
+}
+
+impl<'de> Deserialize<'de> for PayRules {
+    /// Reads the rules, turning an old flat lunch break into the break rule
+    /// that deducts the same time, so saved results stay the same.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let legacy = match value.get("statutory_breaks") {
+            None => LegacyLunch::deserialize(&value).map_err(D::Error::custom)?,
+            Some(_) => LegacyLunch {
+                lunch_minutes: None,
+                lunch_trigger_minutes: 0,
+            },
+        };
+        let mut rules = PayRules::deserialize(value).map_err(D::Error::custom)?;
+        if let Some(minutes) = legacy.lunch_minutes {
+            rules.statutory_breaks = vec![BreakRule {
+                after_minutes: legacy.lunch_trigger_minutes.max(0),
+                minutes: minutes.max(0),
+            }];
+        }
+        Ok(rules)
+    }
+}
+
+impl PayRules {
+    /// The first day of the pay week containing `date`.
+    pub fn week_of(&self, date: NaiveDate) -> NaiveDate {
+        let offset = (date.weekday().num_days_from_monday() + 7
+            - self.week_start.num_days_from_monday())
+            % 7;
+        date - Duration::days(offset as i64)
+    }
+
+    /// The date of weekday `wd` in the pay week starting at `week`.
+    pub fn day_in_week(&self, week: NaiveDate, wd: Weekday) -> NaiveDate {
+        let offset = (wd.num_days_from_monday() + 7 - self.week_start.num_days_from_monday()) % 7;
+        week + Duration::days(offset as i64)
+    }
+
+    /// Whether a minute of the day (0..1440) lies in the night window. The
+    /// window may wrap past midnight, e.g. 22:00–06:00.
+    fn is_night_minute(&self, minute: i32) -> bool {
+        let start = minute_of_day(self.night_start);
+        let end = minute_of_day(self.night_end);
+        if start <= end {
+            start <= minute && minute < end
+        } else {
+            minute >= start || minute < end
+        }
+    }
+
+    /// A clock-in (`clock_in`) or clock-out time after punch rounding.
+    /// Rounding past midnight moves to 00:00 of the next day.
+    pub fn round_punch(&self, t: NaiveDateTime, clock_in: bool) -> NaiveDateTime {
+        let minute = minute_of_day(t.time());
+        let step = match self.punch_rounding {
+            PunchRounding::SevenMinuteRule => 15,
+            _ => self.punch_rounding_step.max(1) as i32,
+        };
+        let rest = minute % step;
+        let down = minute - rest;
+        let up = if rest == 0 { minute } else { down + step };
+        let rounded = match self.punch_rounding {
+            PunchRounding::Off => minute,
+            PunchRounding::Nearest if rest * 2 >= step => up,
+            PunchRounding::Nearest => down,
+            PunchRounding::Up => up,
+            PunchRounding::Down => down,
+            PunchRounding::EmployerFavourable if clock_in => up,
+            PunchRounding::EmployerFavourable => down,
+            PunchRounding::SevenMinuteRule if rest >= 8 => up,
+            PunchRounding::SevenMinuteRule => down,
+        };
+        t.date().and_time(NaiveTime::MIN) + Duration::minutes(rounded as i64)
+    }
+
+    /// How minutes on `date` are paid. A holiday on a weekend is paid at
+    /// whichever of the two multipliers is higher, so a holiday never cuts
+    /// the weekend premium.
+    fn day_kind(&self, date: NaiveDate, holidays: &HolidayCalendar) -> DayKind {
+        let weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+        let weekend_pays_more = weekend && self.weekend_multiplier > self.holiday_multiplier;
+        if holidays.is_holiday(date) && !weekend_pays_more {
+            DayKind::Holiday
+        } else if weekend {
+            DayKind::Weekend
+        } else {
+            DayKind::Workday
+        }
+    }
+
+    /// The statutory break for a shift of `duration` minutes: the longest
+    /// break among the rules the shift is long enough for.
+    pub fn statutory_break(&self, duration: i32) -> i32 {
+        self.statutory_breaks
+            .iter()
+            .filter(|r| duration >= r.after_minutes)
+            .map(|r| r.minutes.max(0))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Short description of the breaks deducted when none are logged.
+    pub fn break_hint(&self) -> String {
+        if self.statutory_breaks.is_empty() {
+            return "No break is deducted unless logged.".into();
+        }
+        let rules: Vec<String> = self
+            .statutory_breaks
+            .iter()
+            .map(|r| match r.after_minutes {
+                0 => format!("{}m on every shift", r.minutes),
+                after => format!("{}m from {}h", r.minutes, after as f64 / 60.0),
+            })
+            .collect();
+        format!("Without logged breaks: {}.", rules.join(", "))
+    }
+
+    /// Short human-readable description of when overtime kicks in.
+    pub fn overtime_hint(&self) -> String {
+        match self.overtime_mode {
+            OvertimeMode::ClockTime => format!(
+                "After {} → {}× overtime.",
+                self.overtime_start.format("%H:%M"),
+                self.overtime_multiplier
+            ),
+            OvertimeMode::DailyHours => format!(
+                "After {}h worked per day → {}× overtime.",
+                self.daily_overtime_after_hours, self.overtime_multiplier
+            ),
+        }
+    }
+}
+
+/* ---------- Hourly rates ---------- */
+
+/// Hourly rate over time. Each entry is paid the rate in effect on its date
+/// unless it carries its own override.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateHistory {
+    /// Rate before the first change.
+    pub base_rate: f64,
+    /// Raises and cuts, each effective from its date on.
+    pub changes: BTreeMap<NaiveDate, f64>,
+}
+
+impl Default for RateHistory {
+    fn default() -> Self {
+        Self {
+            base_rate: 30.0,
+            changes: BTreeMap::new(),
+        }
+    }
+}
+
+impl RateHistory {
+    pub fn rate_on(&self, date: NaiveDate) -> f64 {
+        self.changes
+            .range(..=date)
+            .next_back()
+            .map_or(self.base_rate, |(_, rate)| *rate)
+    }
+
+    /// Rate for an entry on `date`, honouring a per-entry override.
+    pub fn rate_for(&self, date: NaiveDate, entry: &WorkEntry) -> f64 {
+        entry.rate.unwrap_or_else(|| self.rate_on(date))
+    }
+}
+
+/* ---------- Jobs ---------- */
+
+pub type JobId = u32;
+
+/// Colours handed out to new jobs in turn.
+const JOB_PALETTE: [[u8; 3]; 6] = [
+    [70, 120, 200],
+    [220, 120, 40],
+    [60, 160, 90],
+    [170, 80, 180],
+    [200, 60, 60],
+    [40, 150, 160],
+];
+
+/// An employer or position. Every job has its own rates and pay rules, and
+/// overtime and the weekly allowance are worked out per job.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Job {
+    pub id: JobId,
+    pub name: String,
+    /// sRGB colour of the job's entries in the calendar.
+    pub color: [u8; 3],
+    pub rates: RateHistory,
+    pub rules: PayRules,
+}
+
+impl Default for Job {
+    fn default() -> Self {
+        Self::new(0, "Main")
+    }
+}
+
+impl Job {
+    pub fn new(id: JobId, name: &str) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            color: JOB_PALETTE[id as usize % JOB_PALETTE.len()],
+            rates: RateHistory::default(),
+            rules: PayRules::default(),
+        }
+    }
+}
+
+/// Looks a job up by id.
+pub fn find_job(jobs: &[Job], id: JobId) -> Option<&Job> {
+    jobs.iter().find(|j| j.id == id)
+}
+
+/* ---------- Calculation ---------- */
+
+enum DayKind {
+    Workday,
+    Weekend,
+    Holiday,
+}
+
+#[derive(Clone)]
+pub struct PaySummary {
+    /// Start and end of the paid part after punch rounding.
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// Hourly rate the entry was paid at.
+    pub base_rate: Money,
+    pub regular_hours: f64,
+    pub overtime_hours: f64,
+    /// Part of the regular/overtime hours that fell inside the night window.
+    pub night_hours: f64,
+    /// Night premium included in `total_pay`.
+    pub night_pay: Money,
+    /// Part of the regular/overtime hours worked on a Saturday or Sunday.
+    pub weekend_hours: f64,
+    pub weekend_pay: Money,
+    /// Part of the regular/overtime hours worked on a holiday.
+    pub holiday_hours: f64,
+    pub holiday_pay: Money,
+    /// `exact_pay` rounded on its own, as paid under `RoundingPolicy::Entry`.
+    pub total_pay: Money,
+    pub exact_pay: PreciseMoney,
+    /// Under `DayAttribution::SplitAtMidnight`, the part of the shift after
+    /// midnight. It is booked on the next date; the fields above only cover
+    /// the part before midnight.
+    pub carry: Option<Box<PaySummary>>,
+}
+
+impl PaySummary {
+    pub fn total_hours(&self) -> f64 {
+        self.regular_hours + self.overtime_hours
+    }
+
+    /// The whole shift, with any part carried past midnight added back in.
+    pub fn whole(&self) -> PaySummary {
+        let mut whole = self.clone();
+        if let Some(c) = whole.carry.take() {
+            whole.end = c.end;
+            whole.regular_hours += c.regular_hours;
+            whole.overtime_hours += c.overtime_hours;
+            whole.night_hours += c.night_hours;
+            whole.night_pay += c.night_pay;
+            whole.weekend_hours += c.weekend_hours;
+            whole.weekend_pay += c.weekend_pay;
+            whole.holiday_hours += c.holiday_hours;
+            whole.holiday_pay += c.holiday_pay;
+            whole.total_pay += c.total_pay;
+            whole.exact_pay += c.exact_pay;
+        }
+        whole
+    }
+
+    fn price(&mut self, rules: &PayRules) {
+        let rate = self.base_rate;
+        let at = |hours: f64, multiplier: f64| {
+            PreciseMoney::of((hours * 60.0).round() as i64, rate, multiplier)
+        };
+        let night = if rules.night_premium_enabled {
+            at(self.night_hours, rules.night_premium)
+        } else {
+            PreciseMoney::ZERO
+        };
+        let weekend = at(self.weekend_hours, rules.weekend_multiplier - 1.0);
+        let holiday = at(self.holiday_hours, rules.holiday_multiplier - 1.0);
+        self.exact_pay = at(self.regular_hours, 1.0)
+            + at(self.overtime_hours, rules.overtime_multiplier)
+            + night
+            + weekend
+            + holiday;
+        self.night_pay = night.round();
+        self.weekend_pay = weekend.round();
+        self.holiday_pay = holiday.round();
+        self.total_pay = self.exact_pay.round();
+    }
+
+    /// Moves regular hours past a weekly limit into overtime.
+    fn apply_weekly_limit(&mut self, limit: i32, regular_so_far: &mut i32, rules: &PayRules) {
+        let regular = (self.regular_hours * 60.0).round() as i32;
+        let kept = regular.min((limit - *regular_so_far).max(0));
+        *regular_so_far += regular;
+        if kept < regular {
+            let moved = (regular - kept) as f64 / 60.0;
+            self.regular_hours -= moved;
+            self.overtime_hours += moved;
+            self.price(rules);
+        }
+    }
+}
+
+/// Per-entry summaries for every date, aligned with the entry lists.
+pub type PayLedger = HashMap<NaiveDate, Vec<Option<PaySummary>>>;
+
+/// Weekly holiday allowance per job and pay week (keyed by the week's first day).
+pub type WeeklyAllowances = BTreeMap<(JobId, NaiveDate), Money>;
+
+/// Prices every entry, including rules that span more than one date. Each
+/// job is priced with its own rates and rules; entries whose job no longer
+/// exists stay unpriced.
+pub fn calculate_ledger(
+    entries: &HashMap<NaiveDate, Vec<WorkEntry>>,
+    jobs: &[Job],
+    holidays: &HolidayCalendar,
+    currency: Currency,
+) -> PayLedger {
+    let mut ledger: PayLedger = entries
+        .iter()
+        .map(|(date, list)| (*date, list.iter().map(|_| None).collect()))
+        .collect();
+    let mut dates: Vec<NaiveDate> = entries.keys().copied().collect();
+    dates.sort();
+
+    for job in jobs {
+        // 자정 이후로 넘어간 시간은 다음 날 일일 연장근로에 포함
+        let mut carried_in: HashMap<NaiveDate, i32> = HashMap::new();
+        for date in &dates {
+            let seed = carried_in.get(date).copied().unwrap_or(0);
+            let day = ledger.get_mut(date).unwrap();
+            for (i, summary) in
+                calculate_day_pay(*date, &entries[date], job, holidays, currency, seed)
+                    .into_iter()
+                    .enumerate()
+            {
+                if let Some(summary) = summary {
+                    if let Some(carry) = &summary.carry {
+                        *carried_in.entry(*date + Duration::days(1)).or_default() +=
+                            (carry.total_hours() * 60.0).round() as i32;
+                    }
+                    day[i] = Some(summary);
+                }
+            }
+        }
+        if job.rules.weekly_overtime_enabled {
+            apply_weekly_overtime(entries, &mut ledger, job);
+        }
+    }
+    ledger
+}
+
+/// Walks each pay week in chronological order and moves regular hours past
+/// the weekly limit into overtime. Hours that are already overtime (daily or
+/// clock-time) do not count towards the limit a second time.
+fn apply_weekly_overtime(
+    entries: &HashMap<NaiveDate, Vec<WorkEntry>>,
+    ledger: &mut PayLedger,
+    job: &Job,
+) {
+    let rules = &job.rules;
+    let limit = (rules.weekly_overtime_after_hours * 60.0).round() as i32;
+    let mut dates: Vec<NaiveDate> = ledger
+        .keys()
+        .flat_map(|d| [*d, *d + Duration::days(1)])
+        .collect();
+    dates.sort();
+    dates.dedup();
+
+    let mut current_week = None;
+    let mut regular_so_far = 0i32;
+    for date in dates {
+        let week = rules.week_of(date);
+        if current_week != Some(week) {
+            current_week = Some(week);
+            regular_so_far = 0;
+        }
+        // 전날에서 넘어온 부분이 그날 가장 먼저
+        let prev = date - Duration::days(1);
+        if let Some(day) = ledger.get_mut(&prev) {
+            for i in entry_order(&entries[&prev], job.id) {
+                if let Some(carry) = day[i].as_mut().and_then(|s| s.carry.as_mut()) {
+                    carry.apply_weekly_limit(limit, &mut regular_so_far, rules);
+                }
+            }
+        }
+        if let Some(day) = ledger.get_mut(&date) {
+            for i in entry_order(&entries[&date], job.id) {
+                if let Some(summary) = day[i].as_mut() {
+                    summary.apply_weekly_limit(limit, &mut regular_so_far, rules);
+                }
+            }
+        }
+    }
+}
+
+/// Weekly holiday allowance (주휴수당) per job and pay week.
+///
+/// A week qualifies when its regular hours reach `weekly_allowance_min_hours`
+/// and every scheduled day has a valid entry or is a holiday. The allowance
+/// is `min(hours, 40) / 40 × 8` hours at the rate in effect on the last day
+/// of the week, so a full-time week earns one paid 8-hour day.
+pub fn calculate_weekly_allowances(
+    entries: &HashMap<NaiveDate, Vec<WorkEntry>>,
+    ledger: &PayLedger,
+    jobs: &[Job],
+    holidays: &HolidayCalendar,
+    currency: Currency,
+) -> WeeklyAllowances {
+    let mut allowances = WeeklyAllowances::new();
+    for job in jobs {
+        let rules = &job.rules;
+        if !rules.weekly_allowance_enabled {
+            continue;
+        }
+
+        let mut weekly_regular: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        for (date, _, summary) in priced(entries, ledger).filter(|(_, e, _)| e.job == job.id) {
+            *weekly_regular.entry(rules.week_of(date)).or_default() +=
+                (summary.regular_hours * 60.0).round() as i64;
+        }
+
+        for (week, minutes) in weekly_regular {
+            if (minutes as f64) / 60.0 < rules.weekly_allowance_min_hours {
+                continue;
+            }
+            let attended = rules.scheduled_days.iter().all(|wd| {
+                let date = rules.day_in_week(week, *wd);
+                holidays.is_holiday(date)
+                    || priced_on(entries, ledger, date).any(|(_, e, _)| e.job == job.id)
+            });
+            if attended {
+                // 40시간 기준 8시간 → 근무 시간의 1/5
+                let rate = job.rates.rate_on(week + Duration::days(6));
+                let allowance =
+                    PreciseMoney::of(minutes.min(40 * 60), Money::from_major(rate, currency), 0.2);
+                allowances.insert((job.id, week), allowance.round());
+            }
+        }
+    }
+    allowances
+}
+
+/// Pay of priced entries, rounded the way each entry's job rounds pay.
+/// Under `Day` and `Period` the unrounded amounts of a day or pay week
+/// are added up first, so the result matches a payslip to the cent.
+pub fn total_pay<'a>(
+    items: impl IntoIterator<Item = (NaiveDate, &'a WorkEntry, &'a PaySummary)>,
+    jobs: &[Job],
+) -> Money {
+    let mut rounded = Money::ZERO;
+    let mut exact: BTreeMap<(JobId, NaiveDate), PreciseMoney> = BTreeMap::new();
+    for (date, entry, summary) in items {
+        let Some(job) = find_job(jobs, entry.job) else {
+            continue;
+        };
+        let key = match job.rules.rounding {
+            RoundingPolicy::Entry => {
+                rounded += summary.total_pay;
+                continue;
+            }
+            RoundingPolicy::Day => date,
+            RoundingPolicy::Period => job.rules.week_of(date),
+        };
+        *exact.entry((job.id, key)).or_default() += summary.exact_pay;
+    }
+    rounded + exact.into_values().map(PreciseMoney::round).sum::<Money>()
+}
+
+/// Every priced part of every entry with the date it is booked on, in no
+/// particular order.
+pub fn priced<'a>(
+    entries: &'a HashMap<NaiveDate, Vec<WorkEntry>>,
+    ledger: &'a PayLedger,
+) -> impl Iterator<Item = (NaiveDate, &'a WorkEntry, &'a PaySummary)> {
+    ledger.iter().flat_map(move |(date, summaries)| {
+        entries[date]
+            .iter()
+            .zip(summaries)
+            .filter_map(|(e, s)| s.as_ref().map(|s| (e, s)))
+            .flat_map(move |(e, s)| {
+                let next = *date + Duration::days(1);
+                std::iter::once((*date, e, s)).chain(s.carry.as_deref().map(|c| (next, e, c)))
+            })
+    })
+}
+
+/// The priced parts booked on one date: the date's own entries plus parts
+/// carried over midnight from the day before.
+pub fn priced_on<'a>(
+    entries: &'a HashMap<NaiveDate, Vec<WorkEntry>>,
+    ledger: &'a PayLedger,
+    date: NaiveDate,
+) -> impl Iterator<Item = (NaiveDate, &'a WorkEntry, &'a PaySummary)> {
+    let day = move |d: NaiveDate| {
+        entries
+            .get(&d)
+            .into_iter()
+            .flatten()
+            .zip(ledger.get(&d).into_iter().flatten())
+            .filter_map(|(e, s)| s.as_ref().map(|s| (e, s)))
+    };
+    let carried = day(date - Duration::days(1))
+        .filter_map(move |(e, s)| s.carry.as_deref().map(|c| (date, e, c)));
+    carried.chain(day(date).map(move |(e, s)| (date, e, s)))
+}
+
+/// Indices of the entries of `job`, in start-time order.
+fn entry_order(entries: &[WorkEntry], job: JobId) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..entries.len())
+        .filter(|&i| entries[i].job == job)
+        .collect();
+    order.sort_by_key(|&i| entries[i].start);
+    order
+}
+
+/// Summaries for the entries of `job` on one date, in the same order as
+/// `entries`; other jobs' entries are left as `None`.
+///
+/// Entries are evaluated in start-time order so that `OvertimeMode::DailyHours`
+/// can carry the minutes already worked that day into later entries.
+/// `carried_in_minutes` is work booked on this date by shifts that started
+/// the day before.
+pub fn calculate_day_pay(
+    date: NaiveDate,
+    entries: &[WorkEntry],
+    job: &Job,
+    holidays: &HolidayCalendar,
+    currency: Currency,
+    carried_in_minutes: i32,
+) -> Vec<Option<PaySummary>> {
+    let mut summaries: Vec<Option<PaySummary>> = entries.iter().map(|_| None).collect();
+    let mut worked_before = carried_in_minutes;
+    for i in entry_order(entries, job.id) {
+        let e = &entries[i];
+        if let Some(summary) = calculate_pay_summary(
+            e,
+            Money::from_major(job.rates.rate_for(date, e), currency),
+            &job.rules,
+            holidays,
+            worked_before,
+        ) {
+            worked_before += (summary.total_hours() * 60.0).round() as i32;
+            summaries[i] = Some(summary);
+        }
+    }
+    summaries
+}
+
+/// Minutes of one part of a shift, by classification.
+#[derive(Default)]
+struct ShiftMinutes {
+    regular: i32,
+    overtime: i32,
+    night: i32,
+    weekend: i32,
+    holiday: i32,
+}
+
+impl ShiftMinutes {
+    fn worked(&self) -> i32 {
+        self.regular + self.overtime
+    }
+
+    /// Takes an unlogged break out of the regular minutes of both parts of
+    /// a shift first, then out of overtime, so where the shift is booked
+    /// does not change what is deducted.
+    fn deduct_break(parts: &mut [ShiftMinutes], minutes: i32) {
+        let mut rest = minutes;
+        for part in parts.iter_mut() {
+            let taken = rest.min(part.regular);
+            part.regular -= taken;
+            rest -= taken;
+        }
+        for part in parts.iter_mut() {
+            let taken = rest.min(part.overtime);
+            part.overtime -= taken;
+            rest -= taken;
+        }
+    }
+
+    fn into_summary(
+        mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        base_rate: Money,
+        rules: &PayRules,
+        worked_before_minutes: i32,
+    ) -> PaySummary {
+        let worked = self.worked();
+        if rules.overtime_mode == OvertimeMode::DailyHours {
+            let threshold = (rules.daily_overtime_after_hours * 60.0).round() as i32;
+            let regular_left = (threshold - worked_before_minutes).max(0);
+            self.regular = worked.min(regular_left);
+            self.overtime = worked - self.regular;
+        }
+
+        // An unlogged break is assumed to be taken outside the premium windows
+        // where possible.
+        let mut summary = PaySummary {
+            start,
+            end,
+            base_rate,
+            regular_hours: self.regular as f64 / 60.0,
+            overtime_hours: self.overtime as f64 / 60.0,
+            night_hours: self.night.min(worked) as f64 / 60.0,
+            night_pay: Money::ZERO,
+            weekend_hours: self.weekend.min(worked) as f64 / 60.0,
+            weekend_pay: Money::ZERO,
+            holiday_hours: self.holiday.min(worked) as f64 / 60.0,
+            holiday_pay: Money::ZERO,
+            total_pay: Money::ZERO,
+            exact_pay: PreciseMoney::ZERO,
+            carry: None,
+        };
+        summary.price(rules);
+        summary
+    }
+}
+
+/// Pay for a single entry, after rounding its punches. `worked_before_minutes`
+/// is the time already worked earlier on the same date and only matters in
+/// `OvertimeMode::DailyHours`.
+///
+/// Logged unpaid breaks are cut out of the shift where they happened, so a
+/// break after the overtime start reduces overtime. Only a shift without any
+/// logged break gets the statutory break, taken from regular minutes first.
+pub fn calculate_pay_summary(
+    entry: &WorkEntry,
+    base_rate: Money,
+    rules: &PayRules,
+    holidays: &HolidayCalendar,
+    worked_before_minutes: i32,
+) -> Option<PaySummary> {
+    const MINUTES_PER_DAY: i32 = 24 * 60;
+    let overtime_start_min = minute_of_day(rules.overtime_start);
+    let boundaries = [
+        overtime_start_min,
+        minute_of_day(rules.night_start),
+        minute_of_day(rules.night_end),
+    ];
+
+    let start = rules.round_punch(entry.start, true);
+    let end = rules.round_punch(entry.end, false);
+    let date = entry.start.date();
+    let midnight = date.and_time(NaiveTime::MIN);
+    let start_min = (start - midnight).num_minutes() as i32;
+    let end_min = (end - midnight).num_minutes() as i32;
+    let total_duration = end_min - start_min;
+    if total_duration <= 0 {
+        return None;
+    }
+
+    // 휴게시간을 근무 구간 위의 분 단위로 (자정 넘김 포함)
+    let mut unpaid_breaks: Vec<(i32, i32)> = Vec::new();
+    for b in &entry.breaks {
+        let mut b_start = minute_of_day(b.start);
+        let mut b_end = minute_of_day(b.end);
+        if b_start < start_min {
+            b_start += MINUTES_PER_DAY;
+        }
+        if b_end <= b_start {
+            b_end += MINUTES_PER_DAY;
+        }
+        if !b.paid {
+            unpaid_breaks.push((b_start.min(end_min), b_end.min(end_min)));
+        }
+    }
+
+    // 자정 분할이면 다음 날 몫은 두 번째 칸에
+    let split = rules.day_attribution == DayAttribution::SplitAtMidnight;
+    let mut parts = [ShiftMinutes::default(), ShiftMinutes::default()];
+    let mut cursor = start_min;
+
+    // Walk segments that never cross midnight, the overtime start, either
+    // edge of the night window or a break edge, so each segment has a single
+    // classification.
+    while cursor < end_min {
+        let day_start = (cursor / MINUTES_PER_DAY) * MINUTES_PER_DAY;
+        let minute = cursor - day_start;
+        let next_boundary = boundaries
+            .iter()
+            .filter(|&&b| b > minute)
+            .min()
+            .map_or(day_start + MINUTES_PER_DAY, |&b| day_start + b);
+        let next_break_edge = unpaid_breaks
+            .iter()
+            .flat_map(|&(a, b)| [a, b])
+            .filter(|&edge| edge > cursor)
+            .min()
+            .unwrap_or(end_min);
+        let segment_end = end_min.min(next_boundary).min(next_break_edge);
+        let segment = segment_end - cursor;
+        if unpaid_breaks
+            .iter()
+            .any(|&(a, b)| a <= cursor && cursor < b)
+        {
+            cursor = segment_end;
+            continue;
+        }
+
+        let part = &mut parts[usize::from(split && cursor >= MINUTES_PER_DAY)];
+        if minute < overtime_start_min {
+            part.regular += segment;
+        } else {
+            part.overtime += segment;
+        }
+        if rules.is_night_minute(minute) {
+            part.night += segment;
+        }
+        let segment_date = date + Duration::days((cursor / MINUTES_PER_DAY) as i64);
+        match rules.day_kind(segment_date, holidays) {
+            DayKind::Workday => {}
+            DayKind::Weekend => part.weekend += segment,
+            DayKind::Holiday => part.holiday += segment,
+        }
+        cursor = segment_end;
+    }
+
+    if entry.breaks.is_empty() {
+        ShiftMinutes::deduct_break(&mut parts, rules.statutory_break(total_duration));
+    }
+    if parts[0].worked() + parts[1].worked() <= 0 {
+        return None;
+    }
+
+    let [before, after] = parts;
+    let next_midnight = midnight + Duration::days(1);
+    if split && end > next_midnight {
+        let mut summary = before.into_summary(
+            start,
+            next_midnight.max(start),
+            base_rate,
+            rules,
+            worked_before_minutes,
+        );
+        if after.worked() > 0 {
+            let from = next_midnight.max(start);
+            summary.carry = Some(Box::new(after.into_summary(from, end, base_rate, rules, 0)));
+        }
+        Some(summary)
+    } else {
+        Some(before.into_summary(start, end, base_rate, rules, worked_before_minutes))
+    }
+}
+
+fn minute_of_day(t: NaiveTime) -> i32 {
+    (t.num_seconds_from_midnight() / 60) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(date: NaiveDate, start: &str, end: &str) -> WorkEntry {
+        let at = |t: &str| NaiveTime::parse_from_str(t, "%H:%M").unwrap();
+        let (start, end) = (date.and_time(at(start)), date.and_time(at(end)));
+        WorkEntry {
+            start,
+            end: if end <= start { end + Duration::days(1) } else { end },
+            rate: None,
+            job: 0,
+            breaks: Vec::new(),
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn pay(entry: &WorkEntry, rules: &PayRules) -> PaySummary {
+        let rate = Money::from_major(10.0, Currency::Usd);
+        calculate_pay_summary(entry, rate, rules, &HolidayCalendar::default(), 0)
+            .unwrap()
+            .whole()
+    }
+
+    #[test]
+    fn daily_hours_carry_into_a_later_shift() {
+        let mut job = Job::default();
+        job.rules.overtime_mode = OvertimeMode::DailyHours;
+        job.rules.statutory_breaks.clear();
+        let day = date(2025, 3, 4);
+        // 늦은 근무가 목록에서 앞에 있어도 시작 시각 순으로
+        let entries = [entry(day, "12:00", "16:00"), entry(day, "06:00", "11:00")];
+        let summaries = calculate_day_pay(
+            day,
+            &entries,
+            &job,
+            &HolidayCalendar::default(),
+            Currency::Usd,
+            0,
+        );
+        let hours = |i: usize| {
+            let s = summaries[i].as_ref().unwrap();
+            (s.regular_hours, s.overtime_hours)
+        };
+        assert_eq!(hours(1), (5.0, 0.0));
+        assert_eq!(hours(0), (3.0, 1.0));
+    }
+
+    #[test]
+    fn weekly_overtime_starts_mid_shift() {
+        let mut job = Job::default();
+        job.rules.weekly_overtime_enabled = true;
+        job.rules.statutory_breaks.clear();
+        // 월~토 9시간씩: 목요일까지 36시간
+        let monday = date(2025, 3, 10);
+        let entries: HashMap<NaiveDate, Vec<WorkEntry>> = (0..6)
+            .map(|i| monday + Duration::days(i))
+            .map(|d| (d, vec![entry(d, "06:00", "15:00")]))
+            .collect();
+        let ledger = calculate_ledger(
+            &entries,
+            &[job],
+            &HolidayCalendar::default(),
+            Currency::Usd,
+        );
+        let hours = |d: NaiveDate| {
+            let s = ledger[&d][0].as_ref().unwrap();
+            (s.regular_hours, s.overtime_hours)
+        };
+        assert_eq!(hours(monday + Duration::days(3)), (9.0, 0.0));
+        assert_eq!(hours(monday + Duration::days(4)), (4.0, 5.0));
+        assert_eq!(hours(monday + Duration::days(5)), (0.0, 9.0));
+    }
+
+    #[test]
+    fn weekly_allowance_is_a_fifth_of_the_week_up_to_40_hours() {
+        let mut job = Job::default();
+        job.rates.base_rate = 10.0;
+        job.rules.weekly_allowance_enabled = true;
+        job.rules.statutory_breaks.clear();
+        let monday = date(2025, 3, 10);
+        let allowance = |start, end| {
+            let entries: HashMap<NaiveDate, Vec<WorkEntry>> = (0..5)
+                .map(|i| monday + Duration::days(i))
+                .map(|d| (d, vec![entry(d, start, end)]))
+                .collect();
+            let jobs = [job.clone()];
+            let holidays = HolidayCalendar::default();
+            let ledger = calculate_ledger(&entries, &jobs, &holidays, Currency::Usd);
+            calculate_weekly_allowances(&entries, &ledger, &jobs, &holidays, Currency::Usd)
+                [&(0, job.rules.week_of(monday))]
+        };
+        let usd = |amount| Money::from_major(amount, Currency::Usd);
+        // 주 40시간 → 8시간분, 50시간이어도 40시간으로 계산
+        assert_eq!(allowance("07:00", "15:00"), usd(80.0));
+        assert_eq!(allowance("05:00", "15:00"), usd(80.0));
+        // 주 20시간 → 4시간분
+        assert_eq!(allowance("09:00", "13:00"), usd(40.0));
+    }
+
+    #[test]
+    fn rounds_punches_at_the_boundaries() {
+        let day = date(2025, 3, 4);
+        let at = |t: &str| day.and_time(NaiveTime::parse_from_str(t, "%H:%M").unwrap());
+        let seven = PayRules {
+            punch_rounding: PunchRounding::SevenMinuteRule,
+            ..PayRules::default()
+        };
+        assert_eq!(seven.round_punch(at("09:07"), true), at("09:00"));
+        assert_eq!(seven.round_punch(at("09:08"), true), at("09:15"));
+        assert_eq!(seven.round_punch(at("17:07"), false), at("17:00"));
+        assert_eq!(seven.round_punch(at("17:08"), false), at("17:15"));
+        assert_eq!(seven.round_punch(at("23:53"), false), at("00:00") + Duration::days(1));
+
+        let nearest = PayRules {
+            punch_rounding: PunchRounding::Nearest,
+            punch_rounding_step: 6,
+            ..PayRules::default()
+        };
+        assert_eq!(nearest.round_punch(at("09:02"), true), at("09:00"));
+        assert_eq!(nearest.round_punch(at("09:03"), true), at("09:06"));
+        assert_eq!(nearest.round_punch(at("09:07"), false), at("09:06"));
+        assert_eq!(nearest.round_punch(at("09:08"), false), at("09:06"));
+        assert_eq!(nearest.round_punch(at("09:09"), false), at("09:12"));
+    }
+
+    #[test]
+    fn weekend_holiday_keeps_the_higher_multiplier() {
+        let rules = PayRules {
+            weekend_multiplier: 1.5,
+            ..PayRules::default()
+        };
+        // 2025-03-01 is 삼일절 and a Saturday
+        let saturday = pay(&entry(date(2025, 3, 1), "09:00", "13:00"), &rules);
+        assert_eq!(saturday.holiday_hours, 0.0);
+        assert!(saturday.weekend_pay > Money::ZERO);
+
+        let rules = PayRules {
+            holiday_multiplier: 2.0,
+            ..rules
+        };
+        let saturday = pay(&entry(date(2025, 3, 1), "09:00", "13:00"), &rules);
+        assert_eq!(saturday.weekend_hours, 0.0);
+        assert!(saturday.holiday_pay > Money::ZERO);
+    }
+
+    #[test]
+    fn rounds_pay_per_entry_day_or_week() {
+        // Two one-minute shifts on each of two days, 16.68¢ each
+        let monday = date(2025, 3, 3);
+        let entries: Vec<(NaiveDate, WorkEntry)> = [monday, monday + Duration::days(1)]
+            .into_iter()
+            .flat_map(|d| [(d, entry(d, "09:00", "09:01")), (d, entry(d, "10:00", "10:01"))])
+            .collect();
+        let total = |rounding| {
+            let mut job = Job::default();
+            job.rates.base_rate = 10.01;
+            job.rules.statutory_breaks.clear();
+            job.rules.rounding = rounding;
+            let rate = Money::from_major(10.01, Currency::Usd);
+            let holidays = HolidayCalendar::default();
+            let summaries: Vec<PaySummary> = entries
+                .iter()
+                .map(|(_, e)| calculate_pay_summary(e, rate, &job.rules, &holidays, 0).unwrap())
+                .collect();
+            let items = entries.iter().zip(&summaries).map(|((d, e), s)| (*d, e, s));
+            total_pay(items, &[job])
+        };
+        let cents = |c: f64| Money::from_major(c / 100.0, Currency::Usd);
+        assert_eq!(total(RoundingPolicy::Entry), cents(68.0));
+        assert_eq!(total(RoundingPolicy::Day), cents(66.0));
+        assert_eq!(total(RoundingPolicy::Period), cents(67.0));
+    }
+
+    #[test]
+    fn default_breaks_follow_shift_length() {
+        let rules = PayRules::default();
+        let hours = |start, end| pay(&entry(date(2025, 3, 4), start, end), &rules).regular_hours;
+        assert_eq!(hours("09:00", "10:00"), 1.0);
+        assert_eq!(hours("09:00", "14:00"), 4.5);
+        assert_eq!(hours("06:00", "15:00"), 8.0);
+    }
+
+    #[test]
+    fn old_lunch_fields_become_a_break_rule() {
+        let old = r#"{"overtime_multiplier": 2.0, "lunch_minutes": 45,
+                      "lunch_trigger_minutes": 360}"#;
+        let rules: PayRules = serde_json::from_str(old).unwrap();
+        assert_eq!(rules.overtime_multiplier, 2.0);
+        assert_eq!(
+            rules.statutory_breaks,
+            vec![BreakRule {
+                after_minutes: 360,
+                minutes: 45
+            }]
+        );
+
+        // Saved before the trigger existed: always deducted
+        let rules: PayRules = serde_json::from_str(r#"{"lunch_minutes": 30}"#).unwrap();
+        assert_eq!(
+            rules.statutory_breaks,
+            vec![BreakRule {
+                after_minutes: 0,
+                minutes: 30
+            }]
+        );
+
+        // Written again without the old fields, and read back the same
+        let json = serde_json::to_string(&rules).unwrap();
+        assert!(!json.contains("lunch"));
+        assert_eq!(serde_json::from_str::<PayRules>(&json).unwrap(), rules);
+    }
+
+    #[test]
+    fn splitting_at_midnight_keeps_the_shift_total() {
+        let night = PayRules {
+            night_premium_enabled: true,
+            ..PayRules::default()
+        };
+        let split = PayRules {
+            day_attribution: DayAttribution::SplitAtMidnight,
+            ..night.clone()
+        };
+        for (start, end) in [("22:00", "06:00"), ("13:00", "03:00"), ("23:30", "00:30")] {
+            let shift = entry(date(2025, 3, 4), start, end);
+            let (whole, parts) = (pay(&shift, &night), pay(&shift, &split));
+            assert_eq!(parts.total_pay, whole.total_pay, "{}-{}", start, end);
+            assert_eq!(parts.regular_hours, whole.regular_hours, "{}-{}", start, end);
+            assert_eq!(parts.overtime_hours, whole.overtime_hours, "{}-{}", start, end);
+            assert_eq!(parts.night_hours, whole.night_hours, "{}-{}", start, end);
+        }
+        // 22:00–06:00: 2h overtime before midnight, 6h regular after, minus 1h
+        let whole = pay(&entry(date(2025, 3, 4), "22:00", "06:00"), &split);
+        assert_eq!((whole.regular_hours, whole.overtime_hours), (5.0, 2.0));
+    }
+}
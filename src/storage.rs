@@ -0,0 +1,1149 @@
+use crate::backup;
+use crate::csv::{self, Header, Record, Rejected};
+use crate::money::Money;
+use crate::pay::{calculate_ledger, calculate_weekly_allowances, find_job, Job, JobId};
+use crate::times::{hhmm, parse_time, EntryError};
+use crate::{Break, BreakInput, Settings, WorkEntry};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::{params, Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/* ---------- Storage ---------- */
+
+/// What a store saves: the entries and the settings that price them.
+pub struct Snapshot<'a> {
+    pub entries: &'a HashMap<NaiveDate, Vec<WorkEntry>>,
+    pub settings: &'a Settings,
+}
+
+/// What a store read.
+pub struct Loaded {
+    pub entries: HashMap<NaiveDate, Vec<WorkEntry>>,
+    pub report: LoadReport,
+    /// Settings kept in the data file itself; `None` when they live in the
+    /// settings file next to it.
+    pub settings: Option<Settings>,
+}
+
+/// Outcome of reading a data file.
+pub struct LoadReport {
+    pub path: String,
+    pub loaded: usize,
+    pub rejected: Vec<Rejected>,
+}
+
+/// Where the entries are kept. Unless the store keeps settings itself,
+/// they go to a JSON file next to it.
+pub trait Storage {
+    fn path(&self) -> &Path;
+
+    fn format(&self) -> Format;
+
+    fn keeps_settings(&self) -> bool {
+        false
+    }
+
+    /// Reads every entry. Jobs the data mentions but `jobs` lacks are added;
+    /// a store that keeps jobs itself replaces `jobs` with its own.
+    fn load(&mut self, jobs: &mut Vec<Job>) -> Result<Loaded, String>;
+
+    /// Saves `data`. `changed` holds the dates whose entries changed since
+    /// the last save or load; a store may also rewrite everything.
+    fn save(&mut self, data: &Snapshot, changed: &HashSet<NaiveDate>) -> Result<(), String>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Everything in one versioned JSON document.
+    Native,
+    Sqlite,
+    /// Entries with the pay worked out, for spreadsheets.
+    Csv,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Native, Format::Sqlite, Format::Csv];
+
+    /// Picked by file extension; anything unknown is read as CSV.
+    pub fn of(path: &Path) -> Format {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Format::Native,
+            Some("db" | "sqlite" | "sqlite3") => Format::Sqlite,
+            _ => Format::Csv,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Native => "json",
+            Format::Sqlite => "db",
+            Format::Csv => "csv",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Native => "Data file (.json)",
+            Format::Sqlite => "SQLite database (.db)",
+            Format::Csv => "CSV spreadsheet (.csv)",
+        }
+    }
+}
+
+/// The store for `path`, in the format its extension names. The folder is
+/// created if needed so that the file can be saved.
+pub fn open(path: &Path) -> Result<Box<dyn Storage>, String> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    Ok(match Format::of(path) {
+        Format::Native => Box::new(NativeStore::new(path)),
+        Format::Sqlite => Box::new(SqliteStore::open(path)?),
+        Format::Csv => Box::new(CsvStore::new(path)),
+    })
+}
+
+/// The settings file of a store that does not keep settings itself: the
+/// full file name plus ".settings.json", so that "work_data.csv" and
+/// "work_data.db" never share one.
+pub fn settings_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".settings.json");
+    PathBuf::from(name)
+}
+
+/// Where versions that named the settings file after the stem alone kept
+/// the settings of a CSV file.
+pub fn legacy_settings_path(path: &Path) -> Option<PathBuf> {
+    (Format::of(path) == Format::Csv).then(|| path.with_extension("settings.json"))
+}
+
+/* ---------- CSV ---------- */
+
+/// Columns written to CSV. Only `date`, `start` and `end` are needed to load
+/// a file; the others are read when present, in any order.
+const CSV_COLUMNS: [&str; 15] = [
+    "date",
+    "start",
+    "end",
+    "base_rate",
+    "regular_hours",
+    "overtime_hours",
+    "weekend_hours",
+    "holiday_hours",
+    "total",
+    "weekly_allowance",
+    "job",
+    "rounded_start",
+    "rounded_end",
+    "breaks",
+    "end_date",
+];
+
+/// One row per entry, with the pay worked out. The pay columns make the
+/// file useful in a spreadsheet, but mean it is always rewritten whole.
+pub struct CsvStore {
+    path: PathBuf,
+}
+
+impl CsvStore {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl Storage for CsvStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn format(&self) -> Format {
+        Format::Csv
+    }
+
+    fn load(&mut self, jobs: &mut Vec<Job>) -> Result<Loaded, String> {
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            // 처음 실행하면 파일이 없음
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("{}: {}", self.path.display(), e)),
+        };
+        let (entries, report) = read_csv(&text, &self.path.to_string_lossy(), jobs);
+        Ok(Loaded {
+            entries,
+            report,
+            settings: None,
+        })
+    }
+
+    fn save(&mut self, data: &Snapshot, _changed: &HashSet<NaiveDate>) -> Result<(), String> {
+        backup::write_atomic(&self.path, |f| write_csv(f, data))
+            .map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+}
+
+fn write_csv(f: &mut File, data: &Snapshot) -> io::Result<()> {
+    csv::write_record(f, &CSV_COLUMNS)?;
+    let Settings {
+        jobs,
+        holidays,
+        currency,
+        ..
+    } = data.settings;
+    let ledger = calculate_ledger(data.entries, jobs, holidays, *currency);
+    let allowances = calculate_weekly_allowances(data.entries, &ledger, jobs, holidays, *currency);
+
+    let mut dates: Vec<NaiveDate> = data.entries.keys().copied().collect();
+    dates.sort();
+
+    // 주휴수당은 직장별로 그 주의 마지막 행에 기록
+    let week_key = |e: &WorkEntry, date: NaiveDate| {
+        find_job(jobs, e.job).map(|job| (job.id, job.rules.week_of(date)))
+    };
+    let mut last_row_of_week: HashMap<(JobId, NaiveDate), (NaiveDate, usize)> = HashMap::new();
+    for date in &dates {
+        for (i, (e, summary)) in data.entries[date].iter().zip(&ledger[date]).enumerate() {
+            if let (Some(key), Some(_)) = (week_key(e, *date), summary) {
+                last_row_of_week.insert(key, (*date, i));
+            }
+        }
+    }
+
+    let c = *currency;
+    for date in &dates {
+        let list = &data.entries[date];
+        for (i, (e, summary)) in list.iter().zip(&ledger[date]).enumerate() {
+            let job = find_job(jobs, e.job);
+            let mut row = vec![date.to_string(), hhmm(e.start.time()), hhmm(e.end.time())];
+            let rounded = match summary {
+                Some(summary) => {
+                    let summary = summary.whole();
+                    let allowance = match week_key(e, *date) {
+                        Some(key) if last_row_of_week.get(&key) == Some(&(*date, i)) => {
+                            allowances.get(&key).copied().unwrap_or(Money::ZERO)
+                        }
+                        _ => Money::ZERO,
+                    };
+                    row.extend([
+                        c.plain(summary.base_rate),
+                        format!("{:.4}", summary.regular_hours),
+                        format!("{:.4}", summary.overtime_hours),
+                        format!("{:.4}", summary.weekend_hours),
+                        format!("{:.4}", summary.holiday_hours),
+                        c.plain(summary.total_pay),
+                        c.plain(allowance),
+                    ]);
+                    [hhmm(summary.start.time()), hhmm(summary.end.time())]
+                }
+                // 급여를 계산할 수 없는 항목도 남기고 급여 열만 비움
+                None => {
+                    let rate = e.rate.or(job.map(|j| j.rates.rate_on(*date)));
+                    row.push(rate.map_or(String::new(), |r| c.plain(Money::from_major(r, c))));
+                    row.extend(std::iter::repeat_n(String::new(), 6));
+                    Default::default()
+                }
+            };
+            row.push(job.map_or("", |j| &j.name).to_string());
+            row.extend(rounded);
+            row.extend([format_breaks(&e.breaks), e.end.date().to_string()]);
+            csv::write_record(f, &row)?;
+        }
+    }
+    Ok(())
+}
+
+/// Entries in the CSV `text` read from `path`, and the lines that had to be
+/// skipped.
+fn read_csv(
+    text: &str,
+    path: &str,
+    jobs: &mut Vec<Job>,
+) -> (HashMap<NaiveDate, Vec<WorkEntry>>, LoadReport) {
+    let mut entries: HashMap<NaiveDate, Vec<WorkEntry>> = HashMap::new();
+    let mut report = LoadReport {
+        path: path.to_string(),
+        loaded: 0,
+        rejected: Vec::new(),
+    };
+    // 헤더가 없는 예전 파일은 date,start,end,base_rate 순서
+    let mut header = Header::new(&CSV_COLUMNS[..4]);
+    for (i, record) in csv::parse(text).into_iter().enumerate() {
+        let record = match record {
+            Ok(record) => record,
+            Err(rejected) => {
+                report.rejected.push(rejected);
+                continue;
+            }
+        };
+        if i == 0
+            && record
+                .fields
+                .iter()
+                .any(|f| f.trim().eq_ignore_ascii_case("date"))
+        {
+            header = Header::new(&record.fields);
+            if !["date", "start", "end"].iter().all(|c| header.has(c)) {
+                report.rejected.push(Rejected {
+                    line: record.line,
+                    reason: "header needs date, start and end columns".into(),
+                });
+                break;
+            }
+            continue;
+        }
+        match entry_from_record(&header, &record, jobs) {
+            Ok((date, entry)) => {
+                entries.entry(date).or_default().push(entry);
+                report.loaded += 1;
+            }
+            Err(reason) => report.rejected.push(Rejected {
+                line: record.line,
+                reason,
+            }),
+        }
+    }
+    (entries, report)
+}
+
+/// The entry in one data row, or why it cannot be read.
+fn entry_from_record(
+    header: &Header,
+    record: &Record,
+    jobs: &mut Vec<Job>,
+) -> Result<(NaiveDate, WorkEntry), String> {
+    let field = |name: &str| header.get(record, name).unwrap_or("");
+    let date = NaiveDate::parse_from_str(field("date"), "%Y-%m-%d")
+        .map_err(|_| format!("bad date \"{}\"", field("date")))?;
+    let start = parse_time(field("start")).map_err(|e| EntryError::Start(e).to_string())?;
+    let end = parse_time(field("end")).map_err(|e| EntryError::End(e).to_string())?;
+    if start == end {
+        return Err(EntryError::ZeroDuration.to_string());
+    }
+
+    // end_date 열이 없던 파일: 끝이 시작보다 이르면 다음 날
+    let end_date = NaiveDate::parse_from_str(field("end_date"), "%Y-%m-%d")
+        .ok()
+        .filter(|d| *d >= date)
+        .unwrap_or(if end < start {
+            date + Duration::days(1)
+        } else {
+            date
+        });
+    let (start, end) = (date.and_time(start), end_date.and_time(end));
+    let breaks = parse_breaks(field("breaks"), start, end).map_err(|e| e.to_string())?;
+    // 예전 파일에는 job 열이 없음 → 첫 번째 직장
+    let job = match field("job") {
+        "" => jobs[0].id,
+        name => job_named(jobs, name),
+    };
+    // base_rate가 그날의 시급과 다르면 항목별 시급으로 복원
+    let job_rate = find_job(jobs, job).map_or(0.0, |j| j.rates.rate_on(date));
+    let rate = field("base_rate")
+        .parse::<f64>()
+        .ok()
+        .filter(|r| (r - job_rate).abs() > 1e-4);
+    Ok((
+        date,
+        WorkEntry {
+            start,
+            end,
+            rate,
+            job,
+            breaks,
+        },
+    ))
+}
+
+/// The id of the job called `name`, adding a job with default rules if the
+/// file mentions one the settings do not know.
+fn job_named(jobs: &mut Vec<Job>, name: &str) -> JobId {
+    if let Some(job) = jobs.iter().find(|j| j.name == name) {
+        return job.id;
+    }
+    let id = jobs.iter().map(|j| j.id + 1).max().unwrap_or(0);
+    jobs.push(Job::new(id, name));
+    id
+}
+
+/// Breaks as one CSV field: "12:00-12:30;15:00-15:10 paid".
+pub fn format_breaks(breaks: &[Break]) -> String {
+    breaks
+        .iter()
+        .map(|b| {
+            format!(
+                "{}-{}{}",
+                hhmm(b.start),
+                hhmm(b.end),
+                if b.paid { " paid" } else { "" }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Reads breaks written by `format_breaks`, e.g. `12:00-12:30;15:00-15:15 paid`,
+/// for the shift from `start` to `end`.
+pub fn parse_breaks(
+    field: &str,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Result<Vec<Break>, EntryError> {
+    field
+        .split(';')
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .enumerate()
+        .map(|(i, b)| {
+            let (times, paid) = match b.strip_suffix("paid") {
+                Some(times) => (times.trim(), true),
+                None => (b, false),
+            };
+            let (from, to) = times.split_once('-').unwrap_or((times, ""));
+            BreakInput {
+                start: from.into(),
+                end: to.into(),
+                paid,
+            }
+            .parse(i + 1, start, end)
+        })
+        .collect()
+}
+
+/* ---------- Native file ---------- */
+
+/// Version of the native file this build writes. Raise it and add a step to
+/// `migrate` whenever the document changes shape.
+const NATIVE_VERSION: u64 = 1;
+
+/// The native file as read.
+#[derive(Deserialize)]
+struct Document {
+    settings: Settings,
+    entries: BTreeMap<NaiveDate, Vec<WorkEntry>>,
+}
+
+/// The native file as written, borrowing from the app.
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    version: u64,
+    settings: &'a Settings,
+    entries: BTreeMap<&'a NaiveDate, &'a Vec<WorkEntry>>,
+}
+
+/// Entries and settings together in one JSON document with a format
+/// version. Only what was entered is stored; pay is always recalculated.
+pub struct NativeStore {
+    path: PathBuf,
+}
+
+impl NativeStore {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl Storage for NativeStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn format(&self) -> Format {
+        Format::Native
+    }
+
+    fn keeps_settings(&self) -> bool {
+        true
+    }
+
+    fn load(&mut self, _jobs: &mut Vec<Job>) -> Result<Loaded, String> {
+        let fail = |e: &dyn std::fmt::Display| format!("{}: {}", self.path.display(), e);
+        let report = LoadReport {
+            path: self.path.to_string_lossy().into_owned(),
+            loaded: 0,
+            rejected: Vec::new(),
+        };
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            // 처음 실행하면 파일이 없음
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Loaded {
+                    entries: HashMap::new(),
+                    report,
+                    settings: None,
+                })
+            }
+            Err(e) => return Err(fail(&e)),
+        };
+        let doc = serde_json::from_str(&text).map_err(|e| fail(&e))?;
+        let doc: Document =
+            serde_json::from_value(migrate(doc).map_err(|e| fail(&e))?).map_err(|e| fail(&e))?;
+        Ok(Loaded {
+            report: LoadReport {
+                loaded: doc.entries.values().map(Vec::len).sum(),
+                ..report
+            },
+            entries: doc.entries.into_iter().collect(),
+            settings: Some(doc.settings),
+        })
+    }
+
+    fn save(&mut self, data: &Snapshot, _changed: &HashSet<NaiveDate>) -> Result<(), String> {
+        let doc = DocumentRef {
+            version: NATIVE_VERSION,
+            settings: data.settings,
+            entries: data.entries.iter().filter(|(_, l)| !l.is_empty()).collect(),
+        };
+        let json = serde_json::to_string_pretty(&doc).map_err(|e| e.to_string())?;
+        backup::write_atomic(&self.path, |f| f.write_all(json.as_bytes()))
+            .map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+}
+
+/// Brings a document from an older release up to `NATIVE_VERSION`, one
+/// version at a time.
+fn migrate(mut doc: Value) -> Result<Value, String> {
+    let version = doc.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > NATIVE_VERSION {
+        return Err(format!(
+            "made by a newer version of the app (format {})",
+            version
+        ));
+    }
+    for from in version..NATIVE_VERSION {
+        doc = match from {
+            // 0: 버전이 없는 설정 파일(work_data.settings.json)뿐, 항목은 CSV에 있었음.
+            // 예전 점심시간 필드는 PayRules를 읽을 때, 직장 이전 설정은
+            // Settings::upgrade에서 바뀜
+            0 => json!({ "settings": doc, "entries": {} }),
+            _ => unreachable!("no migration from format {}", from),
+        };
+        doc["version"] = json!(from + 1);
+    }
+    Ok(doc)
+}
+
+/* ---------- SQLite ---------- */
+
+/// Schema steps, applied in order; `PRAGMA user_version` counts the steps a
+/// database has had. Add new steps at the end and never change shipped ones.
+const MIGRATIONS: &[&str] = &[
+    // 1: 직장과 시급 이력, 항목과 휴게
+    "CREATE TABLE jobs (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        color TEXT NOT NULL,
+        base_rate REAL NOT NULL,
+        rules TEXT NOT NULL
+    );
+    CREATE TABLE rate_changes (
+        job_id INTEGER NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+        from_date TEXT NOT NULL,
+        rate REAL NOT NULL,
+        PRIMARY KEY (job_id, from_date)
+    );
+    CREATE TABLE entries (
+        id INTEGER PRIMARY KEY,
+        date TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        start_at TEXT NOT NULL,
+        end_at TEXT NOT NULL,
+        rate REAL,
+        job_id INTEGER NOT NULL
+    );
+    CREATE INDEX entries_by_date ON entries(date, position);
+    CREATE TABLE breaks (
+        entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+        start_at TEXT NOT NULL,
+        end_at TEXT NOT NULL,
+        paid INTEGER NOT NULL
+    );",
+];
+
+const DATE_TIME: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Jobs, rate history, entries and breaks in an SQLite database. Only the
+/// dates that changed are rewritten on save.
+pub struct SqliteStore {
+    path: PathBuf,
+    conn: Connection,
+    /// Jobs as last saved or loaded, so unchanged jobs are not rewritten.
+    saved_jobs: Vec<Job>,
+}
+
+impl SqliteStore {
+    /// Opens or creates the database and brings its schema up to date.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let fail = |e: rusqlite::Error| format!("{}: {}", path.display(), e);
+        let mut conn = Connection::open(path).map_err(fail)?;
+        conn.pragma_update(None, "foreign_keys", true)
+            .map_err(fail)?;
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(fail)?;
+        if version as usize > MIGRATIONS.len() {
+            return Err(format!(
+                "{}: made by a newer version of the app (schema {})",
+                path.display(),
+                version
+            ));
+        }
+        for (i, step) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            let tx = conn.transaction().map_err(fail)?;
+            tx.execute_batch(step).map_err(fail)?;
+            tx.pragma_update(None, "user_version", i as i64 + 1)
+                .map_err(fail)?;
+            tx.commit().map_err(fail)?;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            conn,
+            saved_jobs: Vec::new(),
+        })
+    }
+
+    fn read_jobs(&self) -> rusqlite::Result<Vec<Job>> {
+        let mut changes = self
+            .conn
+            .prepare("SELECT job_id, from_date, rate FROM rate_changes")?;
+        let mut rates: HashMap<JobId, Vec<(String, f64)>> = HashMap::new();
+        for row in changes.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))? {
+            let (job, date, rate): (JobId, String, f64) = row?;
+            rates.entry(job).or_default().push((date, rate));
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name, color, base_rate, rules FROM jobs ORDER BY id")?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, JobId>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, f64>(3)?,
+                r.get::<_, String>(4)?,
+            ))
+        })?;
+        let mut jobs = Vec::new();
+        for row in rows {
+            let (id, name, color, base_rate, rules) = row?;
+            let mut job = Job::new(id, &name);
+            if let Some(color) = parse_color(&color) {
+                job.color = color;
+            }
+            // 규칙은 JSON으로 저장, 읽지 못하면 기본 규칙
+            job.rules = serde_json::from_str(&rules).unwrap_or_default();
+            job.rates.base_rate = base_rate;
+            for (date, rate) in rates.remove(&id).unwrap_or_default() {
+                if let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+                    job.rates.changes.insert(date, rate);
+                }
+            }
+            jobs.push(job);
+        }
+        Ok(jobs)
+    }
+
+    fn read_entries(
+        &self,
+        jobs: &[Job],
+    ) -> rusqlite::Result<(HashMap<NaiveDate, Vec<WorkEntry>>, LoadReport)> {
+        let mut report = LoadReport {
+            path: self.path.to_string_lossy().into_owned(),
+            loaded: 0,
+            rejected: Vec::new(),
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT entry_id, start_at, end_at, paid FROM breaks ORDER BY rowid")?;
+        let mut breaks: HashMap<i64, Vec<Result<Break, String>>> = HashMap::new();
+        for row in stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))? {
+            let (entry, start, end, paid): (i64, String, String, bool) = row?;
+            let parse = |s: &str| NaiveTime::parse_from_str(s, "%H:%M").ok();
+            let b = match (parse(&start), parse(&end)) {
+                (Some(start), Some(end)) => Ok(Break { start, end, paid }),
+                _ => Err(format!("bad break \"{}-{}\"", start, end)),
+            };
+            breaks.entry(entry).or_default().push(b);
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, date, start_at, end_at, rate, job_id FROM entries ORDER BY date, position",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, i64>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, Option<f64>>(4)?,
+                r.get::<_, JobId>(5)?,
+            ))
+        })?;
+        let mut entries: HashMap<NaiveDate, Vec<WorkEntry>> = HashMap::new();
+        for row in rows {
+            let (id, date, start, end, rate, job) = row?;
+            let entry_breaks = breaks.remove(&id).unwrap_or_default();
+            let entry = (|| {
+                let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                    .map_err(|_| format!("bad date \"{}\"", date))?;
+                let start = NaiveDateTime::parse_from_str(&start, DATE_TIME)
+                    .map_err(|_| format!("bad start \"{}\"", start))?;
+                let end = NaiveDateTime::parse_from_str(&end, DATE_TIME)
+                    .map_err(|_| format!("bad end \"{}\"", end))?;
+                if find_job(jobs, job).is_none() {
+                    return Err(format!("unknown job {}", job));
+                }
+                let breaks = entry_breaks.into_iter().collect::<Result<_, _>>()?;
+                Ok((
+                    date,
+                    WorkEntry {
+                        start,
+                        end,
+                        rate,
+                        job,
+                        breaks,
+                    },
+                ))
+            })();
+            match entry {
+                Ok((date, entry)) => {
+                    entries.entry(date).or_default().push(entry);
+                    report.loaded += 1;
+                }
+                // 데이터베이스에서는 줄 번호 대신 항목 id
+                Err(reason) => report.rejected.push(Rejected {
+                    line: id as usize,
+                    reason,
+                }),
+            }
+        }
+        Ok((entries, report))
+    }
+}
+
+impl Storage for SqliteStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn format(&self) -> Format {
+        Format::Sqlite
+    }
+
+    fn load(&mut self, jobs: &mut Vec<Job>) -> Result<Loaded, String> {
+        let fail = |e: rusqlite::Error| format!("{}: {}", self.path.display(), e);
+        let stored = self.read_jobs().map_err(fail)?;
+        // 직장이 없는 새 데이터베이스는 설정 파일의 직장을 씀
+        if !stored.is_empty() {
+            *jobs = stored.clone();
+        }
+        let (entries, report) = self.read_entries(jobs).map_err(fail)?;
+        self.saved_jobs = stored;
+        Ok(Loaded {
+            entries,
+            report,
+            settings: None,
+        })
+    }
+
+    fn save(&mut self, data: &Snapshot, changed: &HashSet<NaiveDate>) -> Result<(), String> {
+        let fail = |e: rusqlite::Error| format!("{}: {}", self.path.display(), e);
+        let tx = self.conn.transaction().map_err(fail)?;
+        let jobs = &data.settings.jobs;
+        if self.saved_jobs != *jobs {
+            write_jobs(&tx, jobs).map_err(fail)?;
+        }
+        for date in changed {
+            let list = data.entries.get(date).map_or(&[][..], Vec::as_slice);
+            write_day(&tx, *date, list).map_err(fail)?;
+        }
+        tx.commit().map_err(fail)?;
+        self.saved_jobs = jobs.clone();
+        Ok(())
+    }
+}
+
+fn write_jobs(tx: &Transaction, jobs: &[Job]) -> rusqlite::Result<()> {
+    tx.execute("DELETE FROM jobs", [])?;
+    let mut job_stmt = tx.prepare_cached(
+        "INSERT INTO jobs (id, name, color, base_rate, rules) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    let mut rate_stmt = tx
+        .prepare_cached("INSERT INTO rate_changes (job_id, from_date, rate) VALUES (?1, ?2, ?3)")?;
+    for job in jobs {
+        let [r, g, b] = job.color;
+        let rules = serde_json::to_string(&job.rules).unwrap_or_default();
+        job_stmt.execute(params![
+            job.id,
+            job.name,
+            format!("#{:02x}{:02x}{:02x}", r, g, b),
+            job.rates.base_rate,
+            rules
+        ])?;
+        for (date, rate) in &job.rates.changes {
+            rate_stmt.execute(params![job.id, date.to_string(), rate])?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the entries of `date` with `list`.
+fn write_day(tx: &Transaction, date: NaiveDate, list: &[WorkEntry]) -> rusqlite::Result<()> {
+    tx.execute(
+        "DELETE FROM entries WHERE date = ?1",
+        params![date.to_string()],
+    )?;
+    let mut entry_stmt = tx.prepare_cached(
+        "INSERT INTO entries (date, position, start_at, end_at, rate, job_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    let mut break_stmt = tx.prepare_cached(
+        "INSERT INTO breaks (entry_id, start_at, end_at, paid) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (i, e) in list.iter().enumerate() {
+        entry_stmt.execute(params![
+            date.to_string(),
+            i as i64,
+            e.start.format(DATE_TIME).to_string(),
+            e.end.format(DATE_TIME).to_string(),
+            e.rate,
+            e.job
+        ])?;
+        let id = tx.last_insert_rowid();
+        for b in &e.breaks {
+            break_stmt.execute(params![id, hhmm(b.start), hhmm(b.end), b.paid])?;
+        }
+    }
+    Ok(())
+}
+
+/// "#rrggbb" → sRGB.
+fn parse_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn breaks_must_lie_within_the_shift() {
+        let (start, end) = (at("2025-03-04", "09:00"), at("2025-03-04", "18:00"));
+        let breaks = parse_breaks("12:00-12:30;15:00-15:10 paid", start, end).unwrap();
+        assert_eq!(breaks.len(), 2);
+        assert!(breaks[1].paid);
+        assert_eq!(
+            parse_breaks("12:00-12:30;07:00-07:30", start, end).err(),
+            Some(EntryError::BreakOutsideShift(2))
+        );
+        assert_eq!(
+            parse_breaks("17:45-18:15", start, end).err(),
+            Some(EntryError::BreakOutsideShift(1))
+        );
+
+        // 자정을 넘기는 근무의 휴게
+        let (start, end) = (at("2025-03-04", "22:00"), at("2025-03-05", "06:00"));
+        assert!(parse_breaks("23:30-00:30;02:00-02:30", start, end).is_ok());
+        assert!(parse_breaks("06:00-06:30", start, end).is_err());
+    }
+
+    #[test]
+    fn reads_csv_columns_in_any_order_and_reports_bad_lines() {
+        let text = "job,end,date,start,breaks\n\
+                    Main,18:00,2025-03-04,09:00,12:00-13:00\n\
+                    Main,18:00,2025-03-32,09:00,\n\
+                    \"Night\nshift\",06:00,2025-03-05,22:00,\n\
+                    Main,9,2025-03-06,09:00,\n";
+        let mut jobs = vec![Job::default()];
+        let (entries, report) = read_csv(text, "test.csv", &mut jobs);
+        assert_eq!(report.loaded, 2);
+        let rejected: Vec<(usize, &str)> = report
+            .rejected
+            .iter()
+            .map(|r| (r.line, r.reason.as_str()))
+            .collect();
+        assert_eq!(
+            rejected,
+            vec![
+                (3, "bad date \"2025-03-32\""),
+                (6, "End: missing colon in \"9\" (try 9:00).")
+            ]
+        );
+
+        let day = &entries[&NaiveDate::from_ymd_opt(2025, 3, 4).unwrap()][0];
+        assert_eq!(day.end, at("2025-03-04", "18:00"));
+        assert_eq!(day.breaks.len(), 1);
+        // 새 직장은 기본 규칙으로 추가, 끝이 이르면 다음 날
+        let night = &entries[&NaiveDate::from_ymd_opt(2025, 3, 5).unwrap()][0];
+        assert_eq!(jobs[1].name, "Night\nshift");
+        assert_eq!(night.job, jobs[1].id);
+        assert_eq!(night.end, at("2025-03-06", "06:00"));
+    }
+
+    #[test]
+    fn csv_keeps_entries_without_pay() {
+        let mut job = Job::default();
+        job.rules.punch_rounding = crate::pay::PunchRounding::Nearest;
+        let day = NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+        let entry = |start, end, rate| WorkEntry {
+            start: at("2025-03-04", start),
+            end: at("2025-03-04", end),
+            rate,
+            job: 0,
+            breaks: Vec::new(),
+        };
+        // 09:03–09:07은 15분 단위 반올림 후 0분
+        let entries = HashMap::from([(
+            day,
+            vec![entry("09:03", "09:07", Some(12.5)), entry("10:00", "18:00", None)],
+        )]);
+        let settings = Settings {
+            jobs: vec![job],
+            ..Settings::default()
+        };
+
+        let path = std::env::temp_dir().join(format!("csv_unpriced_{}.csv", std::process::id()));
+        let mut store = CsvStore::new(&path);
+        let data = Snapshot {
+            entries: &entries,
+            settings: &settings,
+        };
+        store.save(&data, &HashSet::new()).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let mut jobs = settings.jobs.clone();
+        let loaded = store.load(&mut jobs).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let row = text.lines().nth(1).unwrap();
+        assert_eq!(row, "2025-03-04,09:03,09:07,12.50,,,,,,,Main,,,,2025-03-04");
+        assert_eq!(loaded.report.loaded, 2);
+        assert_eq!(loaded.entries[&day][0].rate, Some(12.5));
+        assert_eq!(loaded.entries[&day][0].end, at("2025-03-04", "09:07"));
+    }
+
+    fn temp_db(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}_{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    type Shift = (NaiveDateTime, NaiveDateTime, Option<f64>, JobId, usize);
+
+    /// Start, end, rate, job and break count of every entry, for comparing.
+    fn shifts(entries: &HashMap<NaiveDate, Vec<WorkEntry>>) -> BTreeMap<NaiveDate, Vec<Shift>> {
+        entries
+            .iter()
+            .map(|(d, list)| {
+                let list = list
+                    .iter()
+                    .map(|e| (e.start, e.end, e.rate, e.job, e.breaks.len()))
+                    .collect();
+                (*d, list)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn creates_and_upgrades_the_database_schema() {
+        let path = temp_db("sqlite_schema");
+        let version = |path: &Path| -> i64 {
+            let conn = Connection::open(path).unwrap();
+            conn.query_row("PRAGMA user_version", [], |r| r.get(0))
+                .unwrap()
+        };
+        drop(SqliteStore::open(&path).unwrap());
+        assert_eq!(version(&path), MIGRATIONS.len() as i64);
+        // 다시 열어도 이미 적용한 단계는 건너뜀
+        let mut store = SqliteStore::open(&path).unwrap();
+        assert!(store.load(&mut vec![Job::default()]).is_ok());
+        drop(store);
+
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() as i64 + 1)
+            .unwrap();
+        drop(conn);
+        let err = SqliteStore::open(&path).err().unwrap();
+        assert!(err.contains("newer version"), "{}", err);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn sqlite_reads_back_what_it_saved() {
+        let mut main = Job {
+            color: [1, 2, 3],
+            ..Job::default()
+        };
+        main.rates.base_rate = 11.0;
+        main.rates
+            .changes
+            .insert(NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(), 12.5);
+        main.rules.overtime_multiplier = 2.0;
+        let settings = Settings {
+            jobs: vec![main, Job::new(1, "Cafe")],
+            ..Settings::default()
+        };
+        let (tue, wed) = (
+            NaiveDate::from_ymd_opt(2025, 3, 4).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 3, 5).unwrap(),
+        );
+        let lunch = Break {
+            start: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(12, 30, 0).unwrap(),
+            paid: false,
+        };
+        let entries = HashMap::from([
+            (
+                tue,
+                vec![
+                    WorkEntry {
+                        start: at("2025-03-04", "09:00"),
+                        end: at("2025-03-04", "17:00"),
+                        rate: None,
+                        job: 0,
+                        breaks: vec![lunch.clone()],
+                    },
+                    WorkEntry {
+                        start: at("2025-03-04", "18:00"),
+                        end: at("2025-03-04", "21:00"),
+                        rate: Some(9.75),
+                        job: 1,
+                        breaks: Vec::new(),
+                    },
+                ],
+            ),
+            (
+                wed,
+                vec![WorkEntry {
+                    start: at("2025-03-05", "22:00"),
+                    end: at("2025-03-06", "06:00"),
+                    rate: None,
+                    job: 0,
+                    breaks: Vec::new(),
+                }],
+            ),
+        ]);
+
+        let path = temp_db("sqlite_round_trip");
+        let all: HashSet<NaiveDate> = entries.keys().copied().collect();
+        let data = Snapshot {
+            entries: &entries,
+            settings: &settings,
+        };
+        SqliteStore::open(&path).unwrap().save(&data, &all).unwrap();
+        let mut jobs = Vec::new();
+        let loaded = SqliteStore::open(&path).unwrap().load(&mut jobs).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(jobs, settings.jobs);
+        assert_eq!(loaded.report.loaded, 3);
+        assert!(loaded.report.rejected.is_empty());
+        assert_eq!(shifts(&loaded.entries), shifts(&entries));
+        let b = &loaded.entries[&tue][0].breaks[0];
+        assert_eq!((b.start, b.end, b.paid), (lunch.start, lunch.end, lunch.paid));
+    }
+
+    #[test]
+    fn sqlite_rewrites_only_the_changed_dates() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        let shift = |d: u32, start: &str, end: &str| WorkEntry {
+            start: at(&day(d).to_string(), start),
+            end: at(&day(d).to_string(), end),
+            rate: None,
+            job: 0,
+            breaks: Vec::new(),
+        };
+        let settings = Settings {
+            jobs: vec![Job::default()],
+            ..Settings::default()
+        };
+        let mut entries = HashMap::from([
+            (day(3), vec![shift(3, "09:00", "17:00")]),
+            (day(4), vec![shift(4, "09:00", "17:00")]),
+            (day(5), vec![shift(5, "09:00", "17:00")]),
+        ]);
+
+        let path = temp_db("sqlite_incremental");
+        let mut store = SqliteStore::open(&path).unwrap();
+        let all: HashSet<NaiveDate> = entries.keys().copied().collect();
+        let data = Snapshot {
+            entries: &entries,
+            settings: &settings,
+        };
+        store.save(&data, &all).unwrap();
+
+        // 3일은 바꾸고 4일은 지웠지만 저장 대상은 4일뿐
+        entries.insert(day(3), vec![shift(3, "10:00", "12:00")]);
+        entries.remove(&day(4));
+        let data = Snapshot {
+            entries: &entries,
+            settings: &settings,
+        };
+        store.save(&data, &HashSet::from([day(4)])).unwrap();
+        let loaded = SqliteStore::open(&path)
+            .unwrap()
+            .load(&mut vec![Job::default()])
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!loaded.entries.contains_key(&day(4)));
+        assert_eq!(loaded.entries[&day(3)][0].end, at("2025-03-03", "17:00"));
+        assert_eq!(loaded.entries[&day(5)].len(), 1);
+    }
+
+    #[test]
+    fn migrates_a_version_0_settings_file() {
+        // 버전 0 설정 파일: 직장도 휴게 규칙도 없이 점심시간 필드만
+        let old = json!({
+            "rates": { "base_rate": 12.0, "changes": { "2025-01-01": 13.5 } },
+            "pay_rules": { "overtime_multiplier": 2.0, "lunch_minutes": 45,
+                           "lunch_trigger_minutes": 360 },
+            "currency": "Eur"
+        });
+        let doc = migrate(old).unwrap();
+        assert_eq!(doc["version"], json!(NATIVE_VERSION));
+        let doc: Document = serde_json::from_value(doc).unwrap();
+        assert!(doc.entries.is_empty());
+
+        let mut settings = doc.settings;
+        assert_eq!(settings.currency, crate::money::Currency::Eur);
+        settings.upgrade();
+        let job = &settings.jobs[0];
+        assert_eq!(job.rates.rate_on(NaiveDate::from_ymd_opt(2025, 2, 1).unwrap()), 13.5);
+        assert_eq!(job.rules.overtime_multiplier, 2.0);
+        assert_eq!(
+            job.rules.statutory_breaks,
+            vec![crate::pay::BreakRule {
+                after_minutes: 360,
+                minutes: 45
+            }]
+        );
+    }
+
+    #[test]
+    fn refuses_documents_from_a_newer_version() {
+        let doc = json!({ "version": NATIVE_VERSION + 1, "settings": {}, "entries": {} });
+        assert!(migrate(doc).unwrap_err().contains("newer version"));
+        let current = json!({ "version": NATIVE_VERSION, "settings": {}, "entries": {} });
+        assert_eq!(migrate(current.clone()).unwrap(), current);
+    }
+}
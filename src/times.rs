@@ -0,0 +1,222 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use std::fmt;
+
+/* ---------- Clock time parsing ---------- */
+
+/// Why a typed clock time could not be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimeParseError {
+    Empty,
+    /// One or two bare digits: could be an hour or a minute.
+    MissingColon(String),
+    BadHour(String),
+    BadMinute(String),
+    Unrecognized(String),
+}
+
+impl fmt::Display for TimeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeParseError::Empty => write!(f, "no time entered"),
+            TimeParseError::MissingColon(s) => {
+                write!(f, "missing colon in \"{}\" (try {}:00)", s, s)
+            }
+            TimeParseError::BadHour(s) => write!(f, "bad hour \"{}\"", s),
+            TimeParseError::BadMinute(s) => write!(f, "bad minute \"{}\" (00–59)", s),
+            TimeParseError::Unrecognized(s) => write!(f, "\"{}\" is not a time", s),
+        }
+    }
+}
+
+impl std::error::Error for TimeParseError {}
+
+/// Reads a clock time typed by hand: `09:05`, `9:05`, `0905`, `905`, `9am`,
+/// `9:30pm`, `21h` or `21h30`.
+pub fn parse_time(input: &str) -> Result<NaiveTime, TimeParseError> {
+    let s = input.trim().to_lowercase();
+    if s.is_empty() {
+        return Err(TimeParseError::Empty);
+    }
+
+    // 12시간제: 9am, 9:30pm, 1230am
+    for (suffix, offset) in [("am", 0), ("pm", 12)] {
+        if let Some(rest) = s.strip_suffix(suffix) {
+            let rest = rest.trim();
+            let (h, m) = if rest.contains(':') {
+                split_hour_minute(rest)?
+            } else if rest.len() <= 2 {
+                (rest, "0")
+            } else {
+                split_hour_minute(rest)?
+            };
+            let hour = parse_hour(h, 1..=12)?;
+            let minute = parse_minute(m)?;
+            return Ok(clock(hour % 12 + offset, minute));
+        }
+    }
+
+    // 21h, 21h30
+    if let Some((h, m)) = s.split_once('h') {
+        let m = if m.is_empty() { "0" } else { m };
+        return Ok(clock(parse_hour(h, 0..=23)?, parse_minute(m)?));
+    }
+
+    let (h, m) = split_hour_minute(&s)?;
+    Ok(clock(parse_hour(h, 0..=23)?, parse_minute(m)?))
+}
+
+/// "9:05" → ("9", "05"); bare digits "905"/"0905" are split before the last two.
+fn split_hour_minute(s: &str) -> Result<(&str, &str), TimeParseError> {
+    if let Some(parts) = s.split_once(':') {
+        return Ok(parts);
+    }
+    if !s.chars().all(|c| c.is_ascii_digit()) {
+        return Err(TimeParseError::Unrecognized(s.to_string()));
+    }
+    match s.len() {
+        1 | 2 => Err(TimeParseError::MissingColon(s.to_string())),
+        3 | 4 => Ok(s.split_at(s.len() - 2)),
+        _ => Err(TimeParseError::Unrecognized(s.to_string())),
+    }
+}
+
+fn parse_hour(h: &str, range: std::ops::RangeInclusive<u32>) -> Result<u32, TimeParseError> {
+    let h = h.trim();
+    h.parse::<u32>()
+        .ok()
+        .filter(|v| !h.is_empty() && range.contains(v))
+        .ok_or_else(|| TimeParseError::BadHour(h.to_string()))
+}
+
+fn parse_minute(m: &str) -> Result<u32, TimeParseError> {
+    let m = m.trim();
+    m.parse::<u32>()
+        .ok()
+        .filter(|v| (m.len() == 2 || m == "0") && *v < 60)
+        .ok_or_else(|| TimeParseError::BadMinute(m.to_string()))
+}
+
+fn clock(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+/// "HH:MM", the form times are shown and stored in.
+pub fn hhmm(t: NaiveTime) -> String {
+    t.format("%H:%M").to_string()
+}
+
+/// "HH:MM" of a time on or after `day`, marked "+1" (or "+2") when it falls
+/// on a later date.
+pub fn hhmm_on(t: NaiveDateTime, day: NaiveDate) -> String {
+    match (t.date() - day).num_days() {
+        0 => hhmm(t.time()),
+        n => format!("{}+{}", hhmm(t.time()), n),
+    }
+}
+
+/* ---------- Entry validation ---------- */
+
+/// What is wrong with the times typed into the entry form. Breaks are
+/// numbered from 1 as shown.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntryError {
+    Start(TimeParseError),
+    End(TimeParseError),
+    BreakStart(usize, TimeParseError),
+    BreakEnd(usize, TimeParseError),
+    ZeroDuration,
+    /// End before start on the same day, e.g. a 09:00–08:00 typo.
+    EndBeforeStart,
+    LongerThanDay,
+    /// Shares time with the described existing entry.
+    Overlaps(String),
+    ZeroBreak(usize),
+    /// A break that does not lie within the shift.
+    BreakOutsideShift(usize),
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryError::Start(e) => write!(f, "Start: {}.", e),
+            EntryError::End(e) => write!(f, "End: {}.", e),
+            EntryError::BreakStart(n, e) => write!(f, "Break {} start: {}.", n, e),
+            EntryError::BreakEnd(n, e) => write!(f, "Break {} end: {}.", n, e),
+            EntryError::ZeroDuration => write!(f, "Start and end are the same: zero duration."),
+            EntryError::EndBeforeStart => write!(
+                f,
+                "End is before start. Tick \"Ends next day\" for an overnight shift."
+            ),
+            EntryError::LongerThanDay => write!(f, "A shift can be at most 24 hours."),
+            EntryError::Overlaps(other) => write!(f, "Overlaps the entry {}.", other),
+            EntryError::ZeroBreak(n) => write!(f, "Break {} starts and ends at the same time.", n),
+            EntryError::BreakOutsideShift(n) => {
+                write!(f, "Break {} is not within the shift.", n)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EntryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn reads_typed_times() {
+        for (input, time) in [
+            ("09:05", t(9, 5)),
+            ("9:05", t(9, 5)),
+            (" 9:05 ", t(9, 5)),
+            ("0905", t(9, 5)),
+            ("905", t(9, 5)),
+            ("2130", t(21, 30)),
+            ("9am", t(9, 0)),
+            ("9 AM", t(9, 0)),
+            ("12am", t(0, 0)),
+            ("12pm", t(12, 0)),
+            ("9:30pm", t(21, 30)),
+            ("1230am", t(0, 30)),
+            ("21h", t(21, 0)),
+            ("21h30", t(21, 30)),
+            ("0:00", t(0, 0)),
+        ] {
+            assert_eq!(parse_time(input), Ok(time), "{}", input);
+        }
+    }
+
+    #[test]
+    fn explains_what_is_wrong() {
+        use TimeParseError::*;
+        for (input, error) in [
+            ("", Empty),
+            ("  ", Empty),
+            ("9", MissingColon("9".into())),
+            ("21", MissingColon("21".into())),
+            ("25:00", BadHour("25".into())),
+            ("13pm", BadHour("13".into())),
+            ("0am", BadHour("0".into())),
+            ("9:60", BadMinute("60".into())),
+            ("9:5", BadMinute("5".into())),
+            ("24h", BadHour("24".into())),
+            ("21h75", BadMinute("75".into())),
+            ("09055", Unrecognized("09055".into())),
+            ("noon", Unrecognized("noon".into())),
+        ] {
+            assert_eq!(parse_time(input), Err(error), "{}", input);
+        }
+    }
+
+    #[test]
+    fn shows_later_dates_with_a_day_offset() {
+        let day = NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+        assert_eq!(hhmm_on(day.and_time(t(9, 5)), day), "09:05");
+        let next = day.succ_opt().unwrap();
+        assert_eq!(hhmm_on(next.and_time(t(6, 0)), day), "06:00+1");
+    }
+}
@@ -0,0 +1,238 @@
+use crate::backup;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Name of the app's folder in the platform data directory.
+pub const APP_ID: &str = "Money Calendar";
+
+/// Data file names looked for in a profile's folder.
+const DATA_FILES: [&str; 3] = ["work_data.json", "work_data.db", "work_data.csv"];
+
+/// Recent files kept per profile.
+const RECENT_LIMIT: usize = 8;
+
+/// A named set of data files, e.g. one per family member or contract.
+/// Settings live with the data, so every profile has its own.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    /// Most recent first; the first one is opened on start.
+    pub recent: Vec<PathBuf>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: "Default".into(),
+            recent: Vec::new(),
+        }
+    }
+}
+
+/// Profiles and their recent files, kept in the platform data directory.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Workspace {
+    pub profiles: Vec<Profile>,
+    /// Name of the profile in use.
+    pub current: String,
+    /// Why the workspace file could not be read. It is then left alone
+    /// rather than replaced with an empty workspace.
+    #[serde(skip)]
+    pub load_error: Option<String>,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self {
+            profiles: vec![Profile::default()],
+            current: Profile::default().name,
+            load_error: None,
+        }
+    }
+}
+
+/// `~/.local/share/moneycalendar` and its equivalents; the working
+/// directory where the platform has none.
+fn data_dir() -> PathBuf {
+    eframe::storage_dir(APP_ID).unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn workspace_path() -> PathBuf {
+    data_dir().join("workspace.json")
+}
+
+impl Workspace {
+    /// Reads the workspace file. On the first run a data file left in the
+    /// working directory by older versions is put on the recent list. A file
+    /// that cannot be parsed gives an empty workspace with `load_error` set.
+    pub fn load() -> Self {
+        let path = workspace_path();
+        match std::fs::read_to_string(&path) {
+            Ok(json) => {
+                let mut workspace =
+                    serde_json::from_str::<Workspace>(&json).unwrap_or_else(|e| Workspace {
+                        load_error: Some(format!("{}: {}", path.display(), e)),
+                        ..Workspace::default()
+                    });
+                if workspace.profiles.is_empty() {
+                    workspace.profiles.push(Profile::default());
+                }
+                workspace
+            }
+            Err(_) => {
+                let mut workspace = Workspace::default();
+                // 예전에는 작업 폴더에 저장했음
+                if let Some(old) = newest_data_file(Path::new(".")) {
+                    let old = std::path::absolute(&old).unwrap_or(old);
+                    workspace.profiles[0].recent.push(old);
+                }
+                workspace
+            }
+        }
+    }
+
+    /// Writes the workspace file, unless it could not be read on start.
+    pub fn save(&self) -> Result<(), String> {
+        if self.load_error.is_some() {
+            return Ok(());
+        }
+        let path = workspace_path();
+        let fail = |e: &dyn std::fmt::Display| format!("{}: {}", path.display(), e);
+        std::fs::create_dir_all(data_dir()).map_err(|e| fail(&e))?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| fail(&e))?;
+        backup::write_atomic(&path, |f| f.write_all(json.as_bytes())).map_err(|e| fail(&e))
+    }
+
+    pub fn profile(&self) -> &Profile {
+        self.profiles
+            .iter()
+            .find(|p| p.name == self.current)
+            .unwrap_or(&self.profiles[0])
+    }
+
+    fn profile_mut(&mut self) -> &mut Profile {
+        let i = self
+            .profiles
+            .iter()
+            .position(|p| p.name == self.current)
+            .unwrap_or(0);
+        &mut self.profiles[i]
+    }
+
+    /// The file to open for the current profile: the last one used if it is
+    /// still there, otherwise the data file in the profile's folder.
+    pub fn start_file(&self) -> PathBuf {
+        let profile = self.profile();
+        if let Some(path) = profile.recent.iter().find(|p| p.exists()) {
+            return path.clone();
+        }
+        let dir = profile_dir(&profile.name);
+        newest_data_file(&dir).unwrap_or_else(|| dir.join(DATA_FILES[0]))
+    }
+
+    /// Puts `path` first on the current profile's recent list.
+    pub fn remember(&mut self, path: &Path) {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let recent = &mut self.profile_mut().recent;
+        recent.retain(|p| *p != path);
+        recent.insert(0, path);
+        recent.truncate(RECENT_LIMIT);
+    }
+
+    /// Adds an empty profile called `name`.
+    pub fn add_profile(&mut self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Enter a name for the profile.".into());
+        }
+        if self
+            .profiles
+            .iter()
+            .any(|p| p.name.eq_ignore_ascii_case(name))
+        {
+            return Err(format!("There is already a profile called \"{}\".", name));
+        }
+        self.profiles.push(Profile {
+            name: name.to_string(),
+            recent: Vec::new(),
+        });
+        Ok(())
+    }
+}
+
+/// `<data dir>/profiles/<name>`, with characters that cannot be in a file
+/// name replaced.
+fn profile_dir(name: &str) -> PathBuf {
+    let folder: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || " -_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    data_dir().join("profiles").join(folder.trim_matches('.'))
+}
+
+/// The data file in `dir` that was saved last, as converting a file leaves
+/// the old one next to the new one.
+fn newest_data_file(dir: &Path) -> Option<PathBuf> {
+    DATA_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .filter_map(|p| Some((std::fs::metadata(&p).ok()?.modified().ok()?, p)))
+        .max()
+        .map(|(_, p)| p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_duplicate_profile_names_in_any_case() {
+        let mut workspace = Workspace::default();
+        assert!(workspace.add_profile("  Cafe ").is_ok());
+        assert_eq!(workspace.profiles[1].name, "Cafe");
+        assert!(workspace.add_profile("CAFE").is_err());
+        assert!(workspace.add_profile("default").is_err());
+        assert!(workspace.add_profile("   ").is_err());
+        assert_eq!(workspace.profiles.len(), 2);
+    }
+
+    #[test]
+    fn keeps_recent_files_unique_and_short() {
+        let mut workspace = Workspace::default();
+        for i in 0..RECENT_LIMIT + 2 {
+            workspace.remember(Path::new(&format!("/data/{}.json", i)));
+        }
+        workspace.remember(Path::new("/data/5.json"));
+        let recent = &workspace.profile().recent;
+        assert_eq!(recent.len(), RECENT_LIMIT);
+        assert_eq!(recent[0], Path::new("/data/5.json"));
+        assert_eq!(recent.iter().filter(|p| p.ends_with("5.json")).count(), 1);
+        assert!(!recent.iter().any(|p| p.ends_with("0.json")));
+
+        // 현재 프로필의 목록에만
+        workspace.add_profile("Cafe").unwrap();
+        workspace.current = "Cafe".into();
+        workspace.remember(Path::new("/data/cafe.json"));
+        assert_eq!(workspace.profile().recent.len(), 1);
+        assert_eq!(workspace.profiles[0].recent.len(), RECENT_LIMIT);
+    }
+
+    #[test]
+    fn cleans_profile_names_for_folders() {
+        let folder = |name| profile_dir(name).file_name().unwrap().to_owned();
+        assert_eq!(folder("Part time"), "Part time");
+        assert_eq!(folder("a/b:c*"), "a_b_c_");
+        assert_eq!(folder("..hidden."), "hidden");
+        assert_eq!(folder("알바"), "알바");
+        assert_eq!(profile_dir("x").parent(), Some(data_dir().join("profiles").as_path()));
+    }
+}